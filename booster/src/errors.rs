@@ -0,0 +1,116 @@
+use std::sync::{Arc, Mutex};
+use wasmtime::Linker;
+use wasmtime_wasi::p1::WasiP1Ctx;
+
+/// `Store` data for a guest instance: the WASI preview1 context plus this run's own
+/// [`LastError`] slot. Replaces plain `WasiP1Ctx` as every linker's `Store`/`Linker` type
+/// parameter so that `bosbase_host_last_error` reads back only the error from *this* guest's
+/// own run, not whichever concurrent `/run` call happened to set it last — `WasmPool` hands out
+/// one exclusively-leased `Store` per in-flight run, so scoping `LastError` to the `Store` scopes
+/// it to the run.
+pub struct HostState {
+    pub wasi: WasiP1Ctx,
+    pub last_error: Arc<LastError>,
+}
+
+impl HostState {
+    pub fn new(wasi: WasiP1Ctx) -> Self {
+        Self { wasi, last_error: Arc::new(LastError::new()) }
+    }
+}
+
+/// Stable error codes shared by the `redis` and `postgres` host import families. Each import used
+/// to pick its own small, locally-meaningful set of negative numbers, so a guest couldn't tell
+/// "key missing" from "connection lost" from "buffer too small" without reading the host source.
+/// Where a command's own success range overlaps these sentinels (e.g. `redis_incr_by`'s full-`i64`
+/// counters, `redis_ttl`'s native `-1`/`-2` replies), that command keeps its native return
+/// semantics: `redis_ttl` still routes failures through this enum, while `redis_incr_by` reports
+/// every failure as `0`; see the comments on those functions in `redis.rs`. Postgres's transaction-map imports (`pg_commit`, `pg_query_fetch`, ...) return
+/// `NotFound` for a stale or bad handle and `BackendError` for everything else; see
+/// `PostgresHost::tx_handle_error_code` in `postgres.rs`.
+/// `bosbase_host_last_error` supplies the human-readable detail that goes with whichever code
+/// came back.
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostErrorCode {
+    Ok = 0,
+    NotFound = -1,
+    Truncated = -2,
+    BadArgs = -3,
+    BackendUnavailable = -4,
+    BackendError = -5,
+}
+
+impl HostErrorCode {
+    pub fn code(self) -> i32 {
+        self as i32
+    }
+}
+
+/// The human-readable detail behind the most recent `HostErrorCode` failure, fetchable via the
+/// `bosbase_host_last_error` import. Shared across host families (redis, postgres, ...) within a
+/// single guest run via [`HostState`], but NOT across runs: each `/run` call gets its own
+/// `HostState`, so one guest can never read another's error detail (key names, connection
+/// strings, query fragments) even when several runs are in flight concurrently.
+#[derive(Default)]
+pub struct LastError(Mutex<Option<String>>);
+
+impl LastError {
+    pub fn new() -> Self {
+        Self(Mutex::new(None))
+    }
+
+    pub fn set(&self, msg: impl Into<String>) {
+        *self.0.lock().unwrap() = Some(msg.into());
+    }
+
+    pub fn get(&self) -> Option<String> {
+        self.0.lock().unwrap().clone()
+    }
+}
+
+fn write_guest_bytes(
+    caller: &mut wasmtime::Caller<'_, HostState>,
+    ptr: i32,
+    data: &[u8],
+) -> Result<(), ()> {
+    if ptr < 0 {
+        return Err(());
+    }
+    let Some(mem) = caller.get_export("memory").and_then(|e| e.into_memory()) else {
+        return Err(());
+    };
+    mem.write(&mut *caller, ptr as usize, data).map_err(|_| ())?;
+    Ok(())
+}
+
+/// Registers `bosbase_host_last_error(out_ptr, out_len)`, which writes the message behind the
+/// most recent `HostErrorCode` failure into guest memory. Returns the byte length written, `-1`
+/// (`NotFound`) if no failure has been recorded yet, or `-2` (`Truncated`) if `out_len` is too
+/// small.
+pub fn add_host_error_to_linker(linker: &mut Linker<HostState>) -> anyhow::Result<()> {
+    linker.func_wrap_async(
+        "bosbase_host",
+        "bosbase_host_last_error",
+        move |mut caller: wasmtime::Caller<'_, HostState>, (out_ptr, out_len): (i32, i32)| {
+            let last_error = caller.data().last_error.clone();
+            Box::new(async move {
+                if out_len < 0 {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+                let Some(msg) = last_error.get() else {
+                    return Ok(HostErrorCode::NotFound.code());
+                };
+                let bytes = msg.as_bytes();
+                if (bytes.len() as i32) > out_len {
+                    return Ok(HostErrorCode::Truncated.code());
+                }
+                if write_guest_bytes(&mut caller, out_ptr, bytes).is_err() {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+                Ok(bytes.len() as i32)
+            })
+        },
+    )?;
+    Ok(())
+}
@@ -3,30 +3,35 @@ use axum::{
     Json, Router,
     extract::State,
     http::StatusCode,
+    response::sse::{Event, KeepAlive, Sse},
     routing::{get, post},
 };
+use futures_util::stream::{Stream, StreamExt};
 use notify::{RecursiveMode, Watcher};
+mod errors;
 mod pool;
 mod postgres;
 mod redis;
-use pool::WasmPool;
+use pool::{PoolingConfig, RunChunk, WasiConfig, WasmRegistry, configure_pooling};
 use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
+use tokio_stream::wrappers::ReceiverStream;
 use uuid::Uuid;
 use wasmtime::{Config, Engine, Linker, Module};
-use wasmtime_wasi::p1::WasiP1Ctx;
 
 #[tokio::main]
 async fn main() -> Result<(), Error> {
     let state = AppState::new().await?;
 
-    start_wasm_watcher(state.pool.clone());
+    start_wasm_watcher(state.registry.clone());
 
     let app = Router::new()
         .route("/health", get(health_handler))
         .route("/run", post(run_handler))
+        .route("/run/stream", post(run_stream_handler))
         .with_state(state);
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:2678").await?;
@@ -36,7 +41,15 @@ async fn main() -> Result<(), Error> {
 
 #[derive(Clone)]
 struct AppState {
-    pool: WasmPool,
+    /// Keyed by `module_key` (the `.wasm` file's stem), plus a `"default"` alias pointing at
+    /// whichever module is most recently modified, for callers that don't pass `module_key` —
+    /// see `load_all_modules`.
+    registry: WasmRegistry,
+    /// Host directories the operator has chosen to expose into every guest's filesystem via
+    /// `run_with_wasi_config`, parsed once from `BOOSTER_PREOPEN_DIRS` at startup. Deliberately
+    /// not settable per-request: letting an HTTP caller name arbitrary host paths to preopen
+    /// would turn `/run` into an arbitrary-file-read primitive.
+    preopen_dirs: Vec<(PathBuf, String)>,
 }
 
 impl AppState {
@@ -44,6 +57,7 @@ impl AppState {
         let mut config = Config::new();
         config.async_support(true);
         config.consume_fuel(true);
+        config.epoch_interruption(true);
 
         let tune_defaults = std::env::var("BOOSTER_WASMTIME_TUNE_DEFAULTS")
             .ok()
@@ -76,15 +90,28 @@ impl AppState {
             config.memory_reservation_for_growth(v);
         }
 
+        // The pooling instance allocator trades a larger, bounded upfront reservation for
+        // uniformly cheap per-call instantiation; opt-in since the defaults in `PoolingConfig`
+        // won't fit every deployment's guest footprint.
+        let use_pooling_allocator = std::env::var("BOOSTER_WASM_POOLING")
+            .ok()
+            .as_deref()
+            .map(|v| matches!(v, "1" | "true" | "TRUE" | "yes" | "YES"))
+            .unwrap_or(false);
+        if use_pooling_allocator {
+            configure_pooling(&mut config, &PoolingConfig::default());
+        }
+
         let engine = Engine::new(&config)?;
         let wasm_path = default_wasm_path();
-        let module = load_best_module(&engine, &wasm_path)?;
+        let modules = load_all_modules(&engine, &wasm_path)?;
 
-        let mut linker: Linker<WasiP1Ctx> = Linker::new(&engine);
-        wasmtime_wasi::p1::add_to_linker_async(&mut linker, |cx| cx)?;
+        let mut linker: Linker<errors::HostState> = Linker::new(&engine);
+        wasmtime_wasi::p1::add_to_linker_async(&mut linker, |state: &mut errors::HostState| &mut state.wasi)?;
 
         let redis_host = Arc::new(redis::RedisHost::new_from_env().await);
         redis::add_redis_to_linker(&mut linker, redis_host)?;
+        errors::add_host_error_to_linker(&mut linker)?;
 
         let pg_host = Arc::new(postgres::PostgresHost::new_from_env().await);
         postgres::add_postgres_to_linker(&mut linker, pg_host)?;
@@ -94,14 +121,47 @@ impl AppState {
             .and_then(|v| v.parse::<usize>().ok())
             .unwrap_or(8);
 
-        let pool = WasmPool::new(engine, Arc::new(linker), module, max_concurrency);
-        Ok(Self { pool })
+        let linker = Arc::new(linker);
+        let registry = if use_pooling_allocator {
+            WasmRegistry::with_pooling(engine, linker, max_concurrency)
+        } else {
+            WasmRegistry::new(engine, linker, max_concurrency)
+        };
+        for (key, module) in modules.iter().cloned() {
+            registry.set_module(key, module).await;
+        }
+        // `modules` is sorted most-recently-modified first, so this reproduces the old
+        // single-module behavior (run whichever file changed last) for callers that don't pass
+        // `module_key`.
+        let (_, newest) = modules.into_iter().next().expect("load_all_modules returns at least one module");
+        registry.set_module("default", newest).await;
+
+        Ok(Self { registry, preopen_dirs: preopen_dirs_from_env() })
     }
 }
 
 #[derive(Deserialize)]
 struct RunRequest {
     name: String,
+    /// Which registered module to run, keyed by its `.wasm` file's stem (see
+    /// `load_all_modules`). Omit to run whichever module was most recently modified.
+    #[serde(default)]
+    module_key: Option<String>,
+    /// Optional wall-clock deadline in milliseconds, enforced via epoch interruption
+    /// ([`WasmPool::run_with_deadline`]) on top of the looser `BOOSTER_RUN_DEADLINE_MS` backstop.
+    /// Omit to fall back to the plain `run` path. Ignored whenever the run goes through
+    /// [`WasmPool::run_with_wasi_config`] (non-empty `args` or `env`, or `BOOSTER_PREOPEN_DIRS`
+    /// set), which applies no epoch deadline.
+    #[serde(default)]
+    deadline_ms: Option<u64>,
+    /// Extra CLI args for the guest, via [`WasmPool::run_with_wasi_config`]. Takes priority over
+    /// `deadline_ms` if both are set, since that path applies no epoch deadline.
+    #[serde(default)]
+    args: Vec<String>,
+    /// Extra environment variables layered on top of `NAME`, via
+    /// [`WasmPool::run_with_wasi_config`].
+    #[serde(default)]
+    env: std::collections::HashMap<String, String>,
 }
 
 #[derive(Serialize)]
@@ -109,6 +169,7 @@ struct RunResponse {
     stdout: String,
     stderr: String,
     cost: String,
+    fuel: u64,
     trace_id: String,
 }
 
@@ -126,18 +187,42 @@ async fn run_handler(
     Json(req): Json<RunRequest>,
 ) -> Result<Json<RunResponse>, (StatusCode, String)> {
     let trace_id = Uuid::now_v7().simple().to_string();
+    let module_key = req.module_key.as_deref().unwrap_or("default");
+    let pool = state
+        .registry
+        .pool(module_key)
+        .await
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
 
     let started = Instant::now();
-    let (stdout, stderr) = state
-        .pool
-        .run(req.name)
-        .await
+    let run_result = if !req.args.is_empty() || !req.env.is_empty() || !state.preopen_dirs.is_empty() {
+        let wasi = WasiConfig {
+            args: req.args,
+            env: req.env.into_iter().collect(),
+            preopen_dirs: state.preopen_dirs.clone(),
+        };
+        pool.run_with_wasi_config(req.name, wasi).await
+    } else {
+        match req.deadline_ms {
+            Some(ms) => pool
+                .run_with_deadline(req.name, Duration::from_millis(ms))
+                .await
+                .map(|out| (out.stdout, out.stderr, out.fuel_consumed))
+                .map_err(|e| anyhow::anyhow!(e.to_string())),
+            None => pool.run(req.name).await,
+        }
+    };
+    let (stdout, stderr, fuel) = run_result
         .map_err(|err| {
             eprintln!("/run failed trace_id={trace_id} err={err:?}");
             let msg = err.to_string();
             if msg.contains("cannot create a memfd") {
                 let hint = "cannot create a memfd (EPERM): memfd_create was denied. This is usually caused by seccomp/AppArmor/systemd sandboxing or a restricted container environment. If running under a service/container, allow the memfd_create syscall (or relax the sandbox) and retry.";
                 (StatusCode::INTERNAL_SERVER_ERROR, format!("{msg} ({hint})"))
+            } else if msg.contains("fuel limit exceeded") {
+                (StatusCode::TOO_MANY_REQUESTS, msg)
+            } else if msg.contains("exceeded deadline") || msg.contains("exceeded epoch deadline") {
+                (StatusCode::GATEWAY_TIMEOUT, msg)
             } else {
                 (StatusCode::INTERNAL_SERVER_ERROR, msg)
             }
@@ -146,10 +231,104 @@ async fn run_handler(
         stdout,
         stderr,
         cost: format!("{}ms", started.elapsed().as_millis()),
+        fuel,
         trace_id,
     }))
 }
 
+/// Streaming counterpart to `run_handler`: instead of buffering the whole run and returning
+/// stdout/stderr once it completes, forwards each flushed chunk as an SSE event as the guest
+/// executes, followed by a terminal `done` event carrying the trace id, exit status, and final
+/// fuel cost.
+async fn run_stream_handler(
+    State(state): State<AppState>,
+    Json(req): Json<RunRequest>,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let trace_id = Uuid::now_v7().simple().to_string();
+    let module_key = req.module_key.clone().unwrap_or_else(|| "default".to_owned());
+
+    let pool = match state.registry.pool(&module_key).await {
+        Ok(pool) => pool,
+        Err(e) => {
+            let err_event = futures_util::stream::once(async move {
+                Ok(Event::default()
+                    .event("done")
+                    .json_data(serde_json::json!({
+                        "trace_id": trace_id,
+                        "status": "error",
+                        "error": e.to_string(),
+                    }))
+                    .unwrap_or_else(|_| Event::default().event("done").data("serialization error")))
+            });
+            return Sse::new(err_event.boxed()).keep_alive(KeepAlive::default());
+        }
+    };
+
+    // Bounded so a slow SSE client applies real backpressure to the guest instead of letting
+    // buffered chunks grow without limit; see `WasmPool::run_streaming_with_capacity`.
+    let stream_capacity = std::env::var("BOOSTER_STREAM_CAPACITY")
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .unwrap_or(64);
+    let (rx, handle) = pool.run_streaming_with_capacity(req.name, stream_capacity);
+
+    let chunk_events = ReceiverStream::new(rx).map(|chunk| {
+        let event = match chunk {
+            RunChunk::Stdout(bytes) => Event::default()
+                .event("stdout")
+                .data(String::from_utf8_lossy(&bytes).into_owned()),
+            RunChunk::Stderr(bytes) => Event::default()
+                .event("stderr")
+                .data(String::from_utf8_lossy(&bytes).into_owned()),
+        };
+        Ok(event)
+    });
+
+    let done_event = futures_util::stream::once(async move {
+        let payload = match handle.await {
+            Ok(Ok(outcome)) => serde_json::json!({
+                "trace_id": trace_id,
+                "status": "ok",
+                "fuel": outcome.fuel_consumed,
+            }),
+            Ok(Err(err)) => serde_json::json!({
+                "trace_id": trace_id,
+                "status": "error",
+                "error": err.to_string(),
+            }),
+            Err(join_err) => serde_json::json!({
+                "trace_id": trace_id,
+                "status": "error",
+                "error": format!("run task panicked: {join_err}"),
+            }),
+        };
+        Ok(Event::default()
+            .event("done")
+            .json_data(payload)
+            .unwrap_or_else(|_| Event::default().event("done").data("serialization error")))
+    });
+
+    Sse::new(chunk_events.chain(done_event).boxed()).keep_alive(KeepAlive::default())
+}
+
+/// Parses `BOOSTER_PREOPEN_DIRS`, a `;`-separated list of `host_path=guest_path` pairs, into the
+/// directories `run_with_wasi_config` exposes into every guest's filesystem. Unset or empty means
+/// no directories are preopened.
+fn preopen_dirs_from_env() -> Vec<(PathBuf, String)> {
+    std::env::var("BOOSTER_PREOPEN_DIRS")
+        .ok()
+        .map(|v| {
+            v.split(';')
+                .filter(|pair| !pair.is_empty())
+                .filter_map(|pair| {
+                    let (host, guest) = pair.split_once('=')?;
+                    Some((PathBuf::from(host), guest.to_owned()))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 fn default_wasm_path() -> String {
     std::env::var("BOOSTER_PATH").unwrap_or_else(|_| {
         let base_dir = "components/target/wasm32-wasip1/debug/";
@@ -173,35 +352,48 @@ fn list_wasm_candidates(path: &Path) -> Result<Vec<PathBuf>, Error> {
     }
 }
 
-fn load_best_module(engine: &Engine, wasm_path: &str) -> Result<Module, Error> {
-    let path = Path::new(wasm_path);
-    let candidates = list_wasm_candidates(path)?;
-
+/// `list_wasm_candidates`, sorted most-recently-modified first (a file whose mtime can't be read
+/// sorts last, at `UNIX_EPOCH`).
+fn sorted_wasm_candidates(path: &Path) -> Result<Vec<PathBuf>, Error> {
     let mut with_mtime: Vec<(std::time::SystemTime, PathBuf)> = Vec::new();
-    for p in candidates {
-        if let Ok(meta) = std::fs::metadata(&p) {
-            if let Ok(mtime) = meta.modified() {
-                with_mtime.push((mtime, p));
-            } else {
-                with_mtime.push((std::time::SystemTime::UNIX_EPOCH, p));
-            }
-        }
+    for p in list_wasm_candidates(path)? {
+        let mtime = std::fs::metadata(&p)
+            .and_then(|meta| meta.modified())
+            .unwrap_or(std::time::SystemTime::UNIX_EPOCH);
+        with_mtime.push((mtime, p));
     }
-
     with_mtime.sort_by(|a, b| b.0.cmp(&a.0));
-    for (_mtime, p) in with_mtime {
+    Ok(with_mtime.into_iter().map(|(_, p)| p).collect())
+}
+
+/// The `module_key` a guest program's `.wasm` file is registered under in the [`WasmRegistry`]:
+/// its file stem, e.g. `components/foo.wasm` -> `"foo"`.
+fn module_key_from_path(path: &Path) -> String {
+    path.file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("default")
+        .to_owned()
+}
+
+/// Compiles every `.wasm` file under `wasm_path` (skipping ones that fail to load), keyed by
+/// `module_key_from_path`, sorted most-recently-modified first so callers can treat the first
+/// entry as "the" default module the way `run_handler` does when no `module_key` is given.
+fn load_all_modules(engine: &Engine, wasm_path: &str) -> Result<Vec<(String, Module)>, Error> {
+    let path = Path::new(wasm_path);
+    let mut out = Vec::new();
+    for p in sorted_wasm_candidates(path)? {
         match Module::from_file(engine, &p) {
-            Ok(m) => return Ok(m),
-            Err(e) => {
-                eprintln!("Skipping wasm file {:?}: {e}", p);
-            }
+            Ok(m) => out.push((module_key_from_path(&p), m)),
+            Err(e) => eprintln!("Skipping wasm file {:?}: {e}", p),
         }
     }
-
-    Err(anyhow::anyhow!("no valid wasm modules found under {wasm_path}"))
+    if out.is_empty() {
+        return Err(anyhow::anyhow!("no valid wasm modules found under {wasm_path}"));
+    }
+    Ok(out)
 }
 
-fn start_wasm_watcher(pool: WasmPool) {
+fn start_wasm_watcher(registry: WasmRegistry) {
     let wasm_path = default_wasm_path();
     let watch_root = {
         let p = PathBuf::from(&wasm_path);
@@ -242,9 +434,17 @@ fn start_wasm_watcher(pool: WasmPool) {
             tokio::time::sleep(std::time::Duration::from_millis(200)).await;
             while rx.try_recv().is_ok() {}
 
-            match load_best_module(pool.engine(), &wasm_path) {
-                Ok(new_module) => {
-                    pool.update_module(new_module).await;
+            match load_all_modules(registry.engine(), &wasm_path) {
+                Ok(modules) => {
+                    for (key, module) in modules.iter().cloned() {
+                        registry.set_module(key, module).await;
+                    }
+                    // `modules` is sorted most-recently-modified first, so this keeps "default"
+                    // aliased to whichever file changed last, matching pre-registry behavior for
+                    // callers that don't pass `module_key`.
+                    if let Some((_, newest)) = modules.into_iter().next() {
+                        registry.set_module("default".to_owned(), newest).await;
+                    }
                 }
                 Err(e) => {
                     eprintln!("WASM reload skipped: {e}");
@@ -1,21 +1,311 @@
 use anyhow::Error;
+use bytes::Bytes;
+use crate::errors::HostState;
 use std::sync::{
     Arc,
     atomic::{AtomicU64, Ordering},
     Mutex,
 };
-use tokio::sync::{RwLock, Semaphore, OwnedSemaphorePermit};
+use tokio::sync::{mpsc, RwLock, Semaphore, OwnedSemaphorePermit};
 use wasmtime::{Engine, Linker, Module, Store};
-use wasmtime_wasi::{WasiCtx, WasiCtxBuilder, p1::WasiP1Ctx, p2::pipe::MemoryOutputPipe};
+use wasmtime_wasi::{
+    DirPerms, FilePerms, WasiCtx, WasiCtxBuilder,
+    p2::{HostOutputStream, StdoutStream, StreamError, Subscribe, pipe::MemoryOutputPipe},
+};
+
+/// Per-invocation WASI configuration beyond the hardcoded `NAME` env var `run` has always set:
+/// CLI args, extra environment variables, and host directories to expose into the guest's
+/// filesystem. Threaded fresh into a new `WasiCtxBuilder` on every call (stores are recycled
+/// from `free`), so no directory handle, arg, or env leaks between invocations of different
+/// callers. `preopen_dirs` pairs a host path with the guest-visible path it's mounted at.
+#[derive(Clone, Debug, Default)]
+pub struct WasiConfig {
+    pub args: Vec<String>,
+    pub env: Vec<(String, String)>,
+    pub preopen_dirs: Vec<(std::path::PathBuf, String)>,
+}
+
+impl WasiConfig {
+    fn apply(&self, builder: &mut WasiCtxBuilder) -> Result<(), Error> {
+        builder.args(&self.args);
+        builder.envs(&self.env);
+        for (host_path, guest_path) in &self.preopen_dirs {
+            let dir = cap_std::fs::Dir::open_ambient_dir(host_path, cap_std::ambient_authority())?;
+            builder.preopened_dir(dir, DirPerms::all(), FilePerms::all(), guest_path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Per-call overrides for [`WasmPool::run_with_options`]. `fuel_limit: None` and
+/// `yield_interval: 0` fall back to the same `BOOSTER_FUEL_LIMIT`/10000 defaults `run` has
+/// always used, so `RunOptions::default()` reproduces `run`'s prior unconditional behavior.
+#[derive(Clone, Debug, Default)]
+pub struct RunOptions {
+    pub fuel_limit: Option<u64>,
+    pub yield_interval: u64,
+}
+
+/// Outcome of [`WasmPool::run_with_options`]: the collected output, the fuel actually spent, and
+/// whether the guest trapped (as opposed to exiting `_start` normally). A fuel-exhaustion trap is
+/// reported separately as [`WasmError::OutOfFuel`] rather than folded into `trapped`, since a
+/// caller billing or retrying needs to distinguish "ran out of budget" from "guest code trapped".
+pub struct RunOutput {
+    pub stdout: String,
+    pub stderr: String,
+    pub fuel_consumed: u64,
+    pub trapped: bool,
+}
+
+/// Errors from [`WasmPool::run_with_options`]. Fuel exhaustion is split out from the catch-all
+/// so callers can bill or retry a guest that ran out of budget differently from one that failed
+/// to instantiate or genuinely trapped.
+#[derive(Debug)]
+pub enum WasmError {
+    OutOfFuel { consumed: u64, limit: u64 },
+    DeadlineExceeded { deadline: std::time::Duration },
+    Other(Error),
+}
+
+impl std::fmt::Display for WasmError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WasmError::OutOfFuel { consumed, limit } => {
+                write!(f, "fuel limit exceeded: consumed {consumed} of {limit}")
+            }
+            WasmError::DeadlineExceeded { deadline } => {
+                write!(f, "run exceeded epoch deadline of {deadline:?}")
+            }
+            WasmError::Other(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for WasmError {}
+
+impl From<Error> for WasmError {
+    fn from(e: Error) -> Self {
+        WasmError::Other(e)
+    }
+}
+
+/// Periodically calls `engine.increment_epoch()` so `run_with_deadline`'s epoch-based deadlines
+/// actually elapse. One ticker is shared by every clone of a `WasmPool` (spawned once in `new`)
+/// and aborted once the last clone (and its `Arc<EpochTicker>`) drops.
+struct EpochTicker(tokio::task::JoinHandle<()>);
+
+impl Drop for EpochTicker {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+fn spawn_epoch_ticker(engine: Engine, tick: std::time::Duration) -> EpochTicker {
+    let handle = tokio::spawn(async move {
+        let mut interval = tokio::time::interval(tick);
+        loop {
+            interval.tick().await;
+            engine.increment_epoch();
+        }
+    });
+    EpochTicker(handle)
+}
+
+/// One chunk of a streamed run, forwarded as soon as the guest flushes it rather than
+/// buffered until the run completes. See [`WasmPool::run_streaming`].
+pub enum RunChunk {
+    Stdout(Vec<u8>),
+    Stderr(Vec<u8>),
+}
+
+/// Terminal summary of a streamed run, sent once after the last [`RunChunk`] has been
+/// forwarded.
+pub struct RunOutcome {
+    pub fuel_consumed: u64,
+}
+
+/// A [`StdoutStream`] that forwards every flushed write into an `mpsc` channel instead of
+/// buffering it, so a caller can observe guest output as it happens. Cheaply cloneable like
+/// `MemoryOutputPipe`, since `WasiCtxBuilder::stdout`/`stderr` clone their argument per pipe.
+#[derive(Clone)]
+struct ChannelOutputPipe {
+    tx: mpsc::UnboundedSender<RunChunk>,
+    is_stderr: bool,
+}
+
+impl ChannelOutputPipe {
+    fn new(tx: mpsc::UnboundedSender<RunChunk>, is_stderr: bool) -> Self {
+        Self { tx, is_stderr }
+    }
+
+    fn tag(&self, bytes: Vec<u8>) -> RunChunk {
+        if self.is_stderr {
+            RunChunk::Stderr(bytes)
+        } else {
+            RunChunk::Stdout(bytes)
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Subscribe for ChannelOutputPipe {
+    async fn ready(&mut self) {}
+}
+
+impl HostOutputStream for ChannelOutputPipe {
+    fn write(&mut self, bytes: Bytes) -> Result<(), StreamError> {
+        // The receiving end may already be gone if the caller dropped the stream early
+        // (e.g. the SSE client disconnected); that's not a guest-visible failure.
+        let _ = self.tx.send(self.tag(bytes.to_vec()));
+        Ok(())
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        Ok(())
+    }
+
+    fn check_write(&mut self) -> Result<usize, StreamError> {
+        Ok(64 * 1024)
+    }
+}
+
+impl StdoutStream for ChannelOutputPipe {
+    fn stream(&self) -> Box<dyn HostOutputStream> {
+        Box::new(self.clone())
+    }
+
+    fn isatty(&self) -> bool {
+        false
+    }
+}
+
+/// Like [`ChannelOutputPipe`], but backed by a bounded channel so a slow consumer applies real
+/// backpressure to the guest instead of chunks piling up in memory: `check_write` reports no
+/// capacity once the channel is full, and `ready` waits for the receiver to free a slot before
+/// the guest is allowed to write again. See [`WasmPool::run_streaming_with_capacity`].
+#[derive(Clone)]
+struct BoundedChannelOutputPipe {
+    tx: mpsc::Sender<RunChunk>,
+    is_stderr: bool,
+}
+
+impl BoundedChannelOutputPipe {
+    fn new(tx: mpsc::Sender<RunChunk>, is_stderr: bool) -> Self {
+        Self { tx, is_stderr }
+    }
+
+    fn tag(&self, bytes: Vec<u8>) -> RunChunk {
+        if self.is_stderr {
+            RunChunk::Stderr(bytes)
+        } else {
+            RunChunk::Stdout(bytes)
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Subscribe for BoundedChannelOutputPipe {
+    async fn ready(&mut self) {
+        if self.tx.capacity() == 0 {
+            // `reserve` resolves once a slot frees up; dropping the returned permit without
+            // sending releases it again immediately, but that's fine since `write` re-checks
+            // capacity via `try_send` right after `check_write` reports room.
+            let _ = self.tx.reserve().await;
+        }
+    }
+}
+
+impl HostOutputStream for BoundedChannelOutputPipe {
+    fn write(&mut self, bytes: Bytes) -> Result<(), StreamError> {
+        match self.tx.try_send(self.tag(bytes.to_vec())) {
+            Ok(()) => Ok(()),
+            // The receiving end may already be gone (e.g. the consumer disconnected); that's
+            // not a guest-visible failure.
+            Err(mpsc::error::TrySendError::Closed(_)) => Ok(()),
+            Err(mpsc::error::TrySendError::Full(_)) => Err(StreamError::Trap(anyhow::anyhow!(
+                "output channel full despite a prior check_write/ready handshake"
+            ))),
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), StreamError> {
+        Ok(())
+    }
+
+    fn check_write(&mut self) -> Result<usize, StreamError> {
+        if self.tx.capacity() == 0 {
+            Ok(0)
+        } else {
+            Ok(64 * 1024)
+        }
+    }
+}
+
+impl StdoutStream for BoundedChannelOutputPipe {
+    fn stream(&self) -> Box<dyn HostOutputStream> {
+        Box::new(self.clone())
+    }
+
+    fn isatty(&self) -> bool {
+        false
+    }
+}
+
+/// Bounds for the wasmtime pooling instance allocator, passed to [`configure_pooling`] when
+/// building the `Engine` for [`WasmPool::with_pooling`].
+#[derive(Clone, Debug)]
+pub struct PoolingConfig {
+    pub max_instances: u32,
+    pub max_memory_pages: u64,
+    pub max_tables: u32,
+    pub max_table_elements: u32,
+}
+
+impl Default for PoolingConfig {
+    fn default() -> Self {
+        Self {
+            max_instances: 128,
+            // 64 KiB pages; 1024 pages caps each instance's linear memory at 64 MiB.
+            max_memory_pages: 1024,
+            max_tables: 8,
+            max_table_elements: 10_000,
+        }
+    }
+}
+
+/// Configures `config` to use the pooling instance allocator with copy-on-write module images
+/// (`memory_init_cow`) instead of the default on-demand allocator, per `pooling`'s slot bounds.
+/// Call this on the `Config` passed to `Engine::new` before constructing a
+/// [`WasmPool::with_pooling`] from the resulting engine.
+pub fn configure_pooling(config: &mut wasmtime::Config, pooling: &PoolingConfig) {
+    let mut instance_limits = wasmtime::PoolingAllocationConfig::default();
+    instance_limits
+        .total_core_instances(pooling.max_instances)
+        .total_memories(pooling.max_instances)
+        .total_tables(pooling.max_tables)
+        .memory_pages(pooling.max_memory_pages)
+        .table_elements(pooling.max_table_elements);
+    config.allocation_strategy(wasmtime::InstanceAllocationStrategy::Pooling(instance_limits));
+    config.memory_init_cow(true);
+}
 
 #[derive(Clone)]
 pub struct WasmPool {
     engine: Engine,
-    linker: Arc<Linker<WasiP1Ctx>>,
+    linker: Arc<Linker<HostState>>,
     module: Arc<RwLock<Arc<Module>>>,
     generation: Arc<AtomicU64>,
     free: Arc<Mutex<Vec<PooledStore>>>,
     semaphore: Arc<Semaphore>,
+    /// Set by [`WasmPool::with_pooling`]. When true, `run_with_options` skips the manual
+    /// "instance count too high" store-recycling retry, since the pooling allocator's own
+    /// bounded slots make that workaround unnecessary.
+    uses_pooling_allocator: bool,
+    /// Shared background task incrementing `engine`'s epoch for `run_with_deadline`. Kept
+    /// behind an `Arc` so it outlives any single `WasmPool` clone but is aborted once the last
+    /// one drops.
+    epoch_ticker: Arc<EpochTicker>,
+    epoch_tick: std::time::Duration,
 }
 
 #[cfg(test)]
@@ -28,12 +318,16 @@ mod tests {
         let mut config = wasmtime::Config::new();
         config.async_support(true);
         config.consume_fuel(true);
+        // Matches `AppState::new`'s production `Config`: `epoch_interruption` is engine-wide and
+        // enabled unconditionally for `run_with_deadline`, so every other `run_*` path needs to
+        // tolerate it too (see `NEVER_EPOCH_DEADLINE`).
+        config.epoch_interruption(true);
         Engine::new(&config).expect("engine")
     }
 
-    fn new_linker(engine: &Engine) -> Arc<Linker<WasiP1Ctx>> {
+    fn new_linker(engine: &Engine) -> Arc<Linker<HostState>> {
         let mut linker = Linker::new(engine);
-        wasmtime_wasi::p1::add_to_linker_async(&mut linker, |cx| cx).expect("add wasi");
+        wasmtime_wasi::p1::add_to_linker_async(&mut linker, |state: &mut HostState| &mut state.wasi).expect("add wasi");
         Arc::new(linker)
     }
 
@@ -73,7 +367,7 @@ mod tests {
         let module = compile_wasi_module(&engine, "hello");
 
         let pool = WasmPool::new(engine, linker, module, 8);
-        let (stdout, stderr) = pool.run("Sparky".to_owned()).await.expect("run");
+        let (stdout, stderr, _fuel) = pool.run("Sparky".to_owned()).await.expect("run");
         assert!(stdout.contains("hello"));
         assert_eq!(stderr, "");
     }
@@ -87,12 +381,12 @@ mod tests {
         let module2 = compile_wasi_module(&engine, "two");
         let pool = WasmPool::new(engine, linker, module1, 8);
 
-        let (stdout1, _) = pool.run("A".to_owned()).await.expect("run1");
+        let (stdout1, _, _) = pool.run("A".to_owned()).await.expect("run1");
         assert!(stdout1.contains("one"));
 
         pool.update_module(module2).await;
 
-        let (stdout2, _) = pool.run("B".to_owned()).await.expect("run2");
+        let (stdout2, _, _) = pool.run("B".to_owned()).await.expect("run2");
         assert!(stdout2.contains("two"));
     }
 
@@ -139,32 +433,206 @@ mod tests {
 
         assert!(peak.load(Ordering::SeqCst) <= 2);
     }
+
+    #[tokio::test]
+    async fn test_plain_run_survives_engine_epoch_interruption_enabled() {
+        let engine = new_engine();
+        let linker = new_linker(&engine);
+        let module = compile_wasi_module(&engine, "hello");
+
+        let pool = WasmPool::new(engine, linker, module, 8);
+
+        // Let the shared epoch ticker (default 50ms) fire several times before running anything
+        // that never calls `set_epoch_deadline`. A store left at its default deadline of 0 would
+        // trap as soon as the guest hits its first epoch check.
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        let (stdout, stderr, _fuel) = pool.run("Sparky".to_owned()).await.expect("run");
+        assert!(stdout.contains("hello"));
+        assert_eq!(stderr, "");
+    }
+
+    #[tokio::test]
+    async fn test_with_pooling_runs_successfully() {
+        let mut config = wasmtime::Config::new();
+        config.async_support(true);
+        config.consume_fuel(true);
+        configure_pooling(&mut config, &PoolingConfig::default());
+        let engine = Engine::new(&config).expect("engine");
+        let linker = new_linker(&engine);
+        let module = compile_wasi_module(&engine, "pooled");
+
+        let pool = WasmPool::with_pooling(engine, linker, module, 4);
+        let (stdout, stderr, _fuel) = pool.run("Sparky".to_owned()).await.expect("run");
+        assert!(stdout.contains("pooled"));
+        assert_eq!(stderr, "");
+    }
+
+    #[tokio::test]
+    async fn test_run_with_deadline_returns_deadline_exceeded_for_infinite_loop() {
+        let engine = new_engine();
+        let linker = new_linker(&engine);
+        let bytes = wat::parse_str(r#"(module (func $_start (export "_start") (loop $l (br $l))))"#)
+            .expect("wat parse");
+        let module = Module::new(&engine, bytes).expect("module");
+
+        let pool = WasmPool::new(engine, linker, module, 8);
+        let err = pool
+            .run_with_deadline("Sparky".to_owned(), Duration::from_millis(20))
+            .await
+            .expect_err("should exceed deadline");
+        assert!(matches!(err, WasmError::DeadlineExceeded { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_run_streaming_with_capacity_delivers_all_chunks() {
+        let engine = new_engine();
+        let linker = new_linker(&engine);
+        let module = compile_wasi_module(&engine, "stream");
+        let pool = WasmPool::new(engine, linker, module, 8);
+
+        // A capacity of 1 forces the guest's writes through the channel's backpressure path
+        // instead of buffering everything up front.
+        let (mut rx, handle) = pool.run_streaming_with_capacity("Sparky".to_owned(), 1);
+        let mut stdout = Vec::new();
+        while let Some(chunk) = rx.recv().await {
+            if let RunChunk::Stdout(bytes) = chunk {
+                stdout.extend(bytes);
+            }
+        }
+        let outcome = handle.await.expect("join").expect("run");
+        assert!(String::from_utf8_lossy(&stdout).contains("stream"));
+        assert!(outcome.fuel_consumed > 0);
+    }
+
+    #[tokio::test]
+    async fn test_run_with_wasi_config_preopens_dirs_and_sets_args_env() {
+        let engine = new_engine();
+        let linker = new_linker(&engine);
+        let module = compile_wasi_module(&engine, "hello");
+        let pool = WasmPool::new(engine, linker, module, 8);
+
+        let dir = std::env::temp_dir().join(format!("booster-pool-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).expect("mkdir");
+
+        let wasi = WasiConfig {
+            args: vec!["arg0".to_owned()],
+            env: vec![("EXTRA".to_owned(), "1".to_owned())],
+            preopen_dirs: vec![(dir.clone(), "/data".to_owned())],
+        };
+        let (stdout, stderr, _fuel) = pool
+            .run_with_wasi_config("Sparky".to_owned(), wasi)
+            .await
+            .expect("run");
+        assert!(stdout.contains("hello"));
+        assert_eq!(stderr, "");
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
 }
 
 struct PooledStore {
     generation: u64,
     instantiations: u32,
-    store: Store<WasiP1Ctx>,
+    store: Store<HostState>,
 }
 
 pub struct Lease {
     pool: WasmPool,
     generation: u64,
     instantiations: u32,
-    store: Option<Store<WasiP1Ctx>>,
+    store: Option<Store<HostState>>,
     _permit: OwnedSemaphorePermit,
 }
 
+fn epoch_tick_from_env() -> std::time::Duration {
+    std::env::var("BOOSTER_EPOCH_TICK_MS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(std::time::Duration::from_millis)
+        .unwrap_or(std::time::Duration::from_millis(50))
+}
+
+fn fuel_limit_from_env() -> u64 {
+    std::env::var("BOOSTER_FUEL_LIMIT")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .unwrap_or(10_000_000_000)
+}
+
+fn run_deadline_from_env() -> Option<std::time::Duration> {
+    std::env::var("BOOSTER_RUN_DEADLINE_MS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(std::time::Duration::from_millis)
+}
+
+/// Epoch deadline applied to stores that don't use `run_with_deadline`'s wall-clock bounding.
+/// The engine has `epoch_interruption(true)` enabled unconditionally (it's a `Config`-wide
+/// setting shared by every store, on because `run_with_deadline` needs it), so every other store
+/// needs an explicit deadline this far out or it traps as soon as the shared epoch ticker fires
+/// against its default deadline of 0. Half of `u64::MAX` rather than the maximum, since wasmtime
+/// adds this delta to the engine's current epoch.
+const NEVER_EPOCH_DEADLINE: u64 = u64::MAX / 2;
+
+/// Drives `_start` to completion, bounding it by `run_deadline` (the `BOOSTER_RUN_DEADLINE_MS`
+/// wall-clock backstop) if set. The outer `Result` is instantiation/timeout-wiring failure; the
+/// inner one is the guest call's own outcome, which callers interpret differently (some fold a
+/// trap into a reported `trapped: bool`, others propagate it as an error) so it's left un-touched
+/// here.
+async fn call_start(
+    instance: &wasmtime::Instance,
+    store: &mut Store<HostState>,
+    run_deadline: Option<std::time::Duration>,
+) -> Result<Result<(), Error>, Error> {
+    let call = instance
+        .get_typed_func::<(), ()>(&mut *store, "_start")?
+        .call_async(&mut *store, ());
+    Ok(match run_deadline {
+        Some(d) => tokio::time::timeout(d, call)
+            .await
+            .map_err(|_| anyhow::anyhow!("run exceeded deadline of {d:?}"))?,
+        None => call.await,
+    })
+}
+
 impl WasmPool {
-    pub fn new(engine: Engine, linker: Arc<Linker<WasiP1Ctx>>, module: Module, max_concurrency: usize) -> Self {
-        let max = max_concurrency.max(1);
+    pub fn new(engine: Engine, linker: Arc<Linker<HostState>>, module: Module, max_concurrency: usize) -> Self {
+        let semaphore = Arc::new(Semaphore::new(max_concurrency.max(1)));
+        Self::with_semaphore(engine, linker, module, semaphore, false)
+    }
+
+    /// Like [`WasmPool::new`], but for an `engine` whose `Config` was built with
+    /// [`configure_pooling`]: bounded instance/memory/table slots and copy-on-write module
+    /// images make per-call instantiation uniformly cheap, so `run_with_options` no longer needs
+    /// to hand-recycle stores around "instance count too high".
+    pub fn with_pooling(engine: Engine, linker: Arc<Linker<HostState>>, module: Module, max_concurrency: usize) -> Self {
+        let semaphore = Arc::new(Semaphore::new(max_concurrency.max(1)));
+        Self::with_semaphore(engine, linker, module, semaphore, true)
+    }
+
+    /// Like [`WasmPool::new`], but shares a caller-provided `Semaphore` instead of creating its
+    /// own, so several pools (e.g. one per key in [`WasmRegistry`]) can draw instance pressure
+    /// from a single concurrency budget.
+    fn with_semaphore(
+        engine: Engine,
+        linker: Arc<Linker<HostState>>,
+        module: Module,
+        semaphore: Arc<Semaphore>,
+        uses_pooling_allocator: bool,
+    ) -> Self {
+        let epoch_tick = epoch_tick_from_env();
+        let epoch_ticker = Arc::new(spawn_epoch_ticker(engine.clone(), epoch_tick));
         Self {
             engine,
             linker,
             module: Arc::new(RwLock::new(Arc::new(module))),
             generation: Arc::new(AtomicU64::new(0)),
             free: Arc::new(Mutex::new(Vec::new())),
-            semaphore: Arc::new(Semaphore::new(max)),
+            semaphore,
+            uses_pooling_allocator,
+            epoch_ticker,
+            epoch_tick,
         }
     }
 
@@ -185,8 +653,8 @@ impl WasmPool {
         let mut free = self.free.lock().unwrap();
         let (store, instantiations) = match free.pop() {
             Some(pooled) if pooled.generation == generation => (pooled.store, pooled.instantiations),
-            Some(_) => (Store::new(&self.engine, WasiCtx::builder().build_p1()), 0),
-            None => (Store::new(&self.engine, WasiCtx::builder().build_p1()), 0),
+            Some(_) => (Store::new(&self.engine, HostState::new(WasiCtx::builder().build_p1())), 0),
+            None => (Store::new(&self.engine, HostState::new(WasiCtx::builder().build_p1())), 0),
         };
 
         Ok(Lease {
@@ -198,17 +666,92 @@ impl WasmPool {
         })
     }
 
-    pub async fn run(&self, name: String) -> Result<(String, String), Error> {
+    /// Like [`WasmPool::lease`], but also recycles the store if it's past
+    /// `MAX_STORE_INSTANTIATIONS` (unless `with_pooling`'s bounded allocator makes that
+    /// unnecessary) — the prefix every `run_*` variant below shares before installing its own
+    /// pipes/`WasiCtx`. Also resets the store's epoch deadline to "effectively never" on every
+    /// lease: the engine has `epoch_interruption(true)` enabled unconditionally (for
+    /// `run_with_deadline`), so a store left at its default deadline of 0 would trap the instant
+    /// the epoch ticker fires, and a recycled store may still carry a short deadline left behind
+    /// by a prior `run_with_deadline` call. Only `run_with_deadline` overrides this back down.
+    async fn leased_store(&self) -> Result<Lease, Error> {
         let mut lease = self.lease().await?;
-
-        // Wasmtime has an internal per-Store limit on how many instances can be created.
-        // Because we reuse Stores for performance, we must periodically recycle them
-        // to avoid long-run failures under stress.
         const MAX_STORE_INSTANTIATIONS: u32 = 1_000;
-        if lease.instantiations >= MAX_STORE_INSTANTIATIONS {
-            lease.store = Some(Store::new(&self.engine, WasiCtx::builder().build_p1()));
+        if !self.uses_pooling_allocator && lease.instantiations >= MAX_STORE_INSTANTIATIONS {
+            lease.store = Some(Store::new(&self.engine, HostState::new(WasiCtx::builder().build_p1())));
             lease.instantiations = 0;
         }
+        let store = lease.store.as_mut().expect("store present");
+        store.set_epoch_deadline(NEVER_EPOCH_DEADLINE);
+        store.epoch_deadline_async_yield_and_update(NEVER_EPOCH_DEADLINE);
+        Ok(lease)
+    }
+
+    /// Instantiates `module` into `lease`'s store, retrying once by recycling to a fresh store and
+    /// re-applying `fuel_limit`/`yield_interval`/`epoch_ticks` if wasmtime's own per-store instance
+    /// cap is hit (the pooling allocator's bounded slots make this unnecessary, so
+    /// `with_pooling`-backed pools skip the retry). Bumps `lease.instantiations` on success.
+    /// `epoch_ticks: None` means "no deadline", applied as [`NEVER_EPOCH_DEADLINE`] rather than
+    /// left at the fresh store's default of 0, for the same reason `leased_store` resets it.
+    async fn instantiate_with_retry(
+        &self,
+        lease: &mut Lease,
+        module: &Module,
+        fuel_limit: u64,
+        yield_interval: u64,
+        epoch_ticks: Option<u64>,
+    ) -> Result<wasmtime::Instance, Error> {
+        let store = lease.store.as_mut().expect("store present");
+        let instance = match self.linker.instantiate_async(&mut *store, module).await {
+            Ok(i) => i,
+            Err(e) => {
+                let msg = e.to_string();
+                if !self.uses_pooling_allocator && msg.contains("instance count too high") {
+                    *store = Store::new(&self.engine, HostState::new(WasiCtx::builder().build_p1()));
+                    lease.instantiations = 0;
+                    store.set_fuel(fuel_limit)?;
+                    store.fuel_async_yield_interval(Some(yield_interval))?;
+                    match epoch_ticks {
+                        Some(ticks) => {
+                            store.set_epoch_deadline(ticks);
+                            store.epoch_deadline_trap();
+                        }
+                        None => {
+                            store.set_epoch_deadline(NEVER_EPOCH_DEADLINE);
+                            store.epoch_deadline_async_yield_and_update(NEVER_EPOCH_DEADLINE);
+                        }
+                    }
+                    self.linker.instantiate_async(&mut *store, module).await?
+                } else {
+                    return Err(e);
+                }
+            }
+        };
+        lease.instantiations = lease.instantiations.saturating_add(1);
+        Ok(instance)
+    }
+
+    /// Buffered run with the historical defaults (`BOOSTER_FUEL_LIMIT`, a 10000-instruction
+    /// yield interval). Thin wrapper over [`WasmPool::run_with_options`] kept for callers that
+    /// don't need a per-call fuel budget.
+    pub async fn run(&self, name: String) -> Result<(String, String, u64), Error> {
+        let out = self
+            .run_with_options(name, RunOptions::default())
+            .await
+            .map_err(|e| match e {
+                WasmError::OutOfFuel { .. } | WasmError::DeadlineExceeded { .. } => anyhow::anyhow!(e.to_string()),
+                WasmError::Other(err) => err,
+            })?;
+        Ok((out.stdout, out.stderr, out.fuel_consumed))
+    }
+
+    /// Like [`WasmPool::run`], but `opts.fuel_limit`/`opts.yield_interval` override the
+    /// `BOOSTER_FUEL_LIMIT` env var and the fixed 10000-instruction yield interval per call, and
+    /// the result reports whether the guest actually trapped. Because stores are reused from
+    /// `free`, the fuel budget (and deadline) are re-applied on every call, including recycled
+    /// stores and the instantiate-retry path below.
+    pub async fn run_with_options(&self, name: String, opts: RunOptions) -> Result<RunOutput, WasmError> {
+        let mut lease = self.leased_store().await?;
 
         let max_output_bytes = std::env::var("BOOSTER_MAX_OUTPUT_BYTES")
             .ok()
@@ -224,35 +767,282 @@ impl WasmPool {
             .build_p1();
 
         let store = lease.store.as_mut().expect("store present");
-        *store.data_mut() = wasi;
+        *store.data_mut() = HostState::new(wasi);
 
-        store.set_fuel(u64::MAX)?;
-        store.fuel_async_yield_interval(Some(10000))?;
+        // Fuel is a deterministic, machine-independent measure of guest work, unlike
+        // wall-clock time. A fixed budget doubles as a hard ceiling on runaway guests.
+        let fuel_limit = opts.fuel_limit.unwrap_or_else(fuel_limit_from_env);
+        let yield_interval = if opts.yield_interval == 0 { 10_000 } else { opts.yield_interval };
+        store.set_fuel(fuel_limit)?;
+        store.fuel_async_yield_interval(Some(yield_interval))?;
+
+        let run_deadline = run_deadline_from_env();
 
         let module = self.module.read().await.clone();
-        let instance = match self.linker.instantiate_async(&mut *store, &*module).await {
-            Ok(i) => i,
+        let instance = self
+            .instantiate_with_retry(&mut lease, &module, fuel_limit, yield_interval, None)
+            .await?;
+
+        let store = lease.store.as_mut().expect("store present");
+        let call_result = call_start(&instance, store, run_deadline).await?;
+
+        let fuel_consumed = fuel_limit.saturating_sub(store.get_fuel().unwrap_or(0));
+        let trapped = match call_result {
+            Ok(()) => false,
             Err(e) => {
-                let msg = e.to_string();
-                if msg.contains("instance count too high") {
-                    // Recycle store and retry once.
-                    *store = Store::new(&self.engine, WasiCtx::builder().build_p1());
-                    lease.instantiations = 0;
-                    self.linker.instantiate_async(&mut *store, &*module).await?
-                } else {
-                    return Err(e.into());
+                if matches!(e.downcast_ref::<wasmtime::Trap>(), Some(&wasmtime::Trap::OutOfFuel)) {
+                    return Err(WasmError::OutOfFuel { consumed: fuel_consumed, limit: fuel_limit });
+                }
+                if e.downcast_ref::<wasmtime::Trap>().is_none() {
+                    return Err(WasmError::Other(e));
                 }
+                true
             }
         };
-        lease.instantiations = lease.instantiations.saturating_add(1);
-        instance
-            .get_typed_func::<(), ()>(&mut *store, "_start")?
-            .call_async(&mut *store, ())
+
+        let out = String::from_utf8_lossy(stdout_pipe.contents().as_ref()).to_string();
+        let err = String::from_utf8_lossy(stderr_pipe.contents().as_ref()).to_string();
+        Ok(RunOutput { stdout: out, stderr: err, fuel_consumed, trapped })
+    }
+
+    /// Like [`WasmPool::run`], but `wasi` additionally supplies CLI args, extra environment
+    /// variables layered on top of the `NAME` var `run` has always set, and directories exposed
+    /// into the guest's filesystem — turning the pool from a fixed "set NAME, print" demo into a
+    /// general sandboxed executor for real WASI programs.
+    pub async fn run_with_wasi_config(&self, name: String, wasi: WasiConfig) -> Result<(String, String, u64), Error> {
+        let mut lease = self.leased_store().await?;
+
+        let max_output_bytes = std::env::var("BOOSTER_MAX_OUTPUT_BYTES")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(1 << 20);
+        let stdout_pipe = MemoryOutputPipe::new(max_output_bytes);
+        let stderr_pipe = MemoryOutputPipe::new(max_output_bytes);
+
+        let mut builder = WasiCtxBuilder::new();
+        builder
+            .stdout(stdout_pipe.clone())
+            .stderr(stderr_pipe.clone())
+            .env("NAME", &name);
+        wasi.apply(&mut builder)?;
+        let wasi_ctx = builder.build_p1();
+
+        let store = lease.store.as_mut().expect("store present");
+        *store.data_mut() = HostState::new(wasi_ctx);
+
+        let fuel_limit = fuel_limit_from_env();
+        store.set_fuel(fuel_limit)?;
+        store.fuel_async_yield_interval(Some(10000))?;
+
+        let run_deadline = run_deadline_from_env();
+
+        let module = self.module.read().await.clone();
+        let instance = self
+            .instantiate_with_retry(&mut lease, &module, fuel_limit, 10000, None)
             .await?;
 
+        let store = lease.store.as_mut().expect("store present");
+        let call_result = call_start(&instance, store, run_deadline).await?;
+
+        let fuel_consumed = fuel_limit.saturating_sub(store.get_fuel().unwrap_or(0));
+        if let Err(e) = call_result {
+            if matches!(e.downcast_ref::<wasmtime::Trap>(), Some(&wasmtime::Trap::OutOfFuel)) {
+                return Err(anyhow::anyhow!(
+                    "fuel limit exceeded: consumed {fuel_consumed} of {fuel_limit}"
+                ));
+            }
+            return Err(e);
+        }
+
         let out = String::from_utf8_lossy(stdout_pipe.contents().as_ref()).to_string();
         let err = String::from_utf8_lossy(stderr_pipe.contents().as_ref()).to_string();
-        Ok((out, err))
+        Ok((out, err, fuel_consumed))
+    }
+
+    /// Like [`WasmPool::run`], but additionally bounds wall-clock time via wasmtime epoch
+    /// interruption instead of only cooperative fuel yielding: `deadline` is converted into a
+    /// tick count against the shared ticker spawned in `new`, and the guest is interrupted once
+    /// that many ticks have elapsed, surfaced as [`WasmError::DeadlineExceeded`]. This composes
+    /// with fuel limiting (a distinct mechanism) and the `BOOSTER_RUN_DEADLINE_MS` hard timeout,
+    /// which still applies underneath as a backstop.
+    pub async fn run_with_deadline(&self, name: String, deadline: std::time::Duration) -> Result<RunOutput, WasmError> {
+        let mut lease = self.leased_store().await?;
+
+        let max_output_bytes = std::env::var("BOOSTER_MAX_OUTPUT_BYTES")
+            .ok()
+            .and_then(|v| v.parse::<usize>().ok())
+            .unwrap_or(1 << 20);
+        let stdout_pipe = MemoryOutputPipe::new(max_output_bytes);
+        let stderr_pipe = MemoryOutputPipe::new(max_output_bytes);
+
+        let wasi = WasiCtxBuilder::new()
+            .stdout(stdout_pipe.clone())
+            .stderr(stderr_pipe.clone())
+            .env("NAME", &name)
+            .build_p1();
+
+        let store = lease.store.as_mut().expect("store present");
+        *store.data_mut() = HostState::new(wasi);
+
+        let fuel_limit = fuel_limit_from_env();
+        store.set_fuel(fuel_limit)?;
+        store.fuel_async_yield_interval(Some(10_000))?;
+
+        // Round up so a sub-tick deadline still gets at least one tick's grace, and reset the
+        // deadline on every call since stores (and their epoch deadline) are pulled from `free`.
+        // Reaching it traps with `Trap::Interrupt` instead of yielding and extending the deadline.
+        let ticks = deadline.as_millis().div_ceil(self.epoch_tick.as_millis().max(1)).max(1) as u64;
+        store.set_epoch_deadline(ticks);
+        store.epoch_deadline_trap();
+
+        let run_deadline = run_deadline_from_env();
+
+        let module = self.module.read().await.clone();
+        let instance = self
+            .instantiate_with_retry(&mut lease, &module, fuel_limit, 10_000, Some(ticks))
+            .await?;
+
+        let store = lease.store.as_mut().expect("store present");
+        let call_result = call_start(&instance, store, run_deadline).await?;
+
+        let fuel_consumed = fuel_limit.saturating_sub(store.get_fuel().unwrap_or(0));
+        let trapped = match call_result {
+            Ok(()) => false,
+            Err(e) => {
+                if matches!(e.downcast_ref::<wasmtime::Trap>(), Some(&wasmtime::Trap::OutOfFuel)) {
+                    return Err(WasmError::OutOfFuel { consumed: fuel_consumed, limit: fuel_limit });
+                }
+                match e.downcast_ref::<wasmtime::Trap>() {
+                    Some(&wasmtime::Trap::Interrupt) => {
+                        return Err(WasmError::DeadlineExceeded { deadline });
+                    }
+                    Some(_) => true,
+                    None => return Err(WasmError::Other(e)),
+                }
+            }
+        };
+
+        let out = String::from_utf8_lossy(stdout_pipe.contents().as_ref()).to_string();
+        let err = String::from_utf8_lossy(stderr_pipe.contents().as_ref()).to_string();
+        Ok(RunOutput { stdout: out, stderr: err, fuel_consumed, trapped })
+    }
+
+    /// Like [`WasmPool::run`], but hands back a channel of [`RunChunk`]s forwarded as the guest
+    /// flushes stdout/stderr, instead of collecting everything into a `MemoryOutputPipe` and
+    /// returning it only once the run completes. The returned `JoinHandle` resolves to the
+    /// terminal [`RunOutcome`] (or error) once the run is done; callers should drain the
+    /// receiver to completion before awaiting it.
+    pub fn run_streaming(&self, name: String) -> (mpsc::UnboundedReceiver<RunChunk>, tokio::task::JoinHandle<Result<RunOutcome, Error>>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let pool = self.clone();
+        let handle = tokio::spawn(async move { pool.run_streaming_inner(name, tx).await });
+        (rx, handle)
+    }
+
+    async fn run_streaming_inner(&self, name: String, tx: mpsc::UnboundedSender<RunChunk>) -> Result<RunOutcome, Error> {
+        let mut lease = self.leased_store().await?;
+
+        let stdout_pipe = ChannelOutputPipe::new(tx.clone(), false);
+        let stderr_pipe = ChannelOutputPipe::new(tx, true);
+
+        let wasi = WasiCtxBuilder::new()
+            .stdout(stdout_pipe)
+            .stderr(stderr_pipe)
+            .env("NAME", &name)
+            .build_p1();
+
+        let store = lease.store.as_mut().expect("store present");
+        *store.data_mut() = HostState::new(wasi);
+
+        let fuel_limit = fuel_limit_from_env();
+        store.set_fuel(fuel_limit)?;
+        store.fuel_async_yield_interval(Some(10000))?;
+
+        let run_deadline = run_deadline_from_env();
+
+        let module = self.module.read().await.clone();
+        let instance = self
+            .instantiate_with_retry(&mut lease, &module, fuel_limit, 10000, None)
+            .await?;
+
+        let store = lease.store.as_mut().expect("store present");
+        let call_result = call_start(&instance, store, run_deadline).await;
+        // The store goes back to `free` with its `HostState`, so drop the WASI context now: its
+        // pipes hold `tx` clones, and the receiver only sees the channel close once they are gone.
+        *store.data_mut() = HostState::new(WasiCtx::builder().build_p1());
+        let call_result = call_result?;
+
+        let fuel_consumed = fuel_limit.saturating_sub(store.get_fuel().unwrap_or(0));
+        if let Err(e) = call_result {
+            if matches!(e.downcast_ref::<wasmtime::Trap>(), Some(&wasmtime::Trap::OutOfFuel)) {
+                return Err(anyhow::anyhow!(
+                    "fuel limit exceeded: consumed {fuel_consumed} of {fuel_limit}"
+                ));
+            }
+            return Err(e);
+        }
+
+        Ok(RunOutcome { fuel_consumed })
+    }
+
+    /// Like [`WasmPool::run_streaming`], but the channel is bounded to `capacity` chunks so a
+    /// slow consumer throttles the guest (via [`BoundedChannelOutputPipe`]'s `check_write`/
+    /// `ready`) instead of chunks piling up in memory without limit.
+    pub fn run_streaming_with_capacity(
+        &self,
+        name: String,
+        capacity: usize,
+    ) -> (mpsc::Receiver<RunChunk>, tokio::task::JoinHandle<Result<RunOutcome, Error>>) {
+        let (tx, rx) = mpsc::channel(capacity.max(1));
+        let pool = self.clone();
+        let handle = tokio::spawn(async move { pool.run_streaming_bounded_inner(name, tx).await });
+        (rx, handle)
+    }
+
+    async fn run_streaming_bounded_inner(&self, name: String, tx: mpsc::Sender<RunChunk>) -> Result<RunOutcome, Error> {
+        let mut lease = self.leased_store().await?;
+
+        let stdout_pipe = BoundedChannelOutputPipe::new(tx.clone(), false);
+        let stderr_pipe = BoundedChannelOutputPipe::new(tx, true);
+
+        let wasi = WasiCtxBuilder::new()
+            .stdout(stdout_pipe)
+            .stderr(stderr_pipe)
+            .env("NAME", &name)
+            .build_p1();
+
+        let store = lease.store.as_mut().expect("store present");
+        *store.data_mut() = HostState::new(wasi);
+
+        let fuel_limit = fuel_limit_from_env();
+        store.set_fuel(fuel_limit)?;
+        store.fuel_async_yield_interval(Some(10000))?;
+
+        let run_deadline = run_deadline_from_env();
+
+        let module = self.module.read().await.clone();
+        let instance = self
+            .instantiate_with_retry(&mut lease, &module, fuel_limit, 10000, None)
+            .await?;
+
+        let store = lease.store.as_mut().expect("store present");
+        let call_result = call_start(&instance, store, run_deadline).await;
+        // The store goes back to `free` with its `HostState`, so drop the WASI context now: its
+        // pipes hold `tx` clones, and the receiver only sees the channel close once they are gone.
+        *store.data_mut() = HostState::new(WasiCtx::builder().build_p1());
+        let call_result = call_result?;
+
+        let fuel_consumed = fuel_limit.saturating_sub(store.get_fuel().unwrap_or(0));
+        if let Err(e) = call_result {
+            if matches!(e.downcast_ref::<wasmtime::Trap>(), Some(&wasmtime::Trap::OutOfFuel)) {
+                return Err(anyhow::anyhow!(
+                    "fuel limit exceeded: consumed {fuel_consumed} of {fuel_limit}"
+                ));
+            }
+            return Err(e);
+        }
+
+        Ok(RunOutcome { fuel_consumed })
     }
 }
 
@@ -271,3 +1061,103 @@ impl Drop for Lease {
         });
     }
 }
+
+/// Hosts several independently-updatable modules behind one shared `Engine`, `Linker`, and
+/// concurrency `Semaphore`. A single [`WasmPool`] only ever holds one module: `update_module`
+/// bumps its one `generation` counter and clears its whole `free` list, so a deployment hosting
+/// several guest programs would have to flush every other program's warm stores just to reload
+/// one. `WasmRegistry` instead keeps one [`WasmPool`] per key, so `update_module(key, ...)` only
+/// invalidates that key's pooled stores — each pool already tracks its own generation and free
+/// list independently — while every key still draws from the same `Semaphore`, so total in-flight
+/// instance pressure across all of them stays globally bounded.
+#[derive(Clone)]
+pub struct WasmRegistry {
+    engine: Engine,
+    linker: Arc<Linker<HostState>>,
+    semaphore: Arc<Semaphore>,
+    uses_pooling_allocator: bool,
+    pools: Arc<RwLock<std::collections::HashMap<String, WasmPool>>>,
+}
+
+impl WasmRegistry {
+    pub fn new(engine: Engine, linker: Arc<Linker<HostState>>, max_concurrency: usize) -> Self {
+        Self {
+            engine,
+            linker,
+            semaphore: Arc::new(Semaphore::new(max_concurrency.max(1))),
+            uses_pooling_allocator: false,
+            pools: Arc::new(RwLock::new(std::collections::HashMap::new())),
+        }
+    }
+
+    /// Like [`WasmRegistry::new`], but for an `engine` whose `Config` was built with
+    /// [`configure_pooling`]; every key's pool is created with `uses_pooling_allocator` set, the
+    /// same way [`WasmPool::with_pooling`] does for a single pool.
+    pub fn with_pooling(engine: Engine, linker: Arc<Linker<HostState>>, max_concurrency: usize) -> Self {
+        Self {
+            uses_pooling_allocator: true,
+            ..Self::new(engine, linker, max_concurrency)
+        }
+    }
+
+    /// Registers `module` under `key`. If `key` is new, a fresh [`WasmPool`] is created for it
+    /// (sharing this registry's `Engine`/`Linker`/`Semaphore`); if `key` already exists, this is
+    /// equivalent to that pool's `update_module` — only `key`'s generation and free list are
+    /// invalidated, leaving every other key's warm stores untouched.
+    pub async fn set_module(&self, key: impl Into<String>, module: Module) {
+        let key = key.into();
+        let mut pools = self.pools.write().await;
+        match pools.get(&key) {
+            Some(pool) => pool.update_module(module).await,
+            None => {
+                let pool = WasmPool::with_semaphore(
+                    self.engine.clone(),
+                    self.linker.clone(),
+                    module,
+                    self.semaphore.clone(),
+                    self.uses_pooling_allocator,
+                );
+                pools.insert(key, pool);
+            }
+        }
+    }
+
+    /// Like [`WasmRegistry::set_module`], but fails instead of creating `key` if it isn't
+    /// already registered.
+    pub async fn update_module(&self, key: &str, module: Module) -> Result<(), Error> {
+        let pools = self.pools.read().await;
+        let pool = pools
+            .get(key)
+            .ok_or_else(|| anyhow::anyhow!("unknown module key {key:?}"))?;
+        pool.update_module(module).await;
+        Ok(())
+    }
+
+    /// Routes `run` to `key`'s pool.
+    pub async fn run(&self, key: &str, name: String) -> Result<(String, String, u64), Error> {
+        let pool = self.pool(key).await?;
+        pool.run(name).await
+    }
+
+    /// Hands back `key`'s pool (cheaply cloneable — see [`WasmPool`]) so callers that need a
+    /// run variant this registry doesn't wrap directly (`run_with_deadline`,
+    /// `run_with_wasi_config`, the streaming variants) can call it themselves.
+    pub async fn pool(&self, key: &str) -> Result<WasmPool, Error> {
+        self.pools
+            .read()
+            .await
+            .get(key)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("unknown module key {key:?}"))
+    }
+
+    pub fn engine(&self) -> &Engine {
+        &self.engine
+    }
+
+    /// Every key currently registered, for callers that need to iterate all of them rather than
+    /// route to one.
+    pub async fn keys(&self) -> Vec<String> {
+        self.pools.read().await.keys().cloned().collect()
+    }
+}
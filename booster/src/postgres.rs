@@ -1,17 +1,105 @@
 use anyhow::Error;
 use base64::Engine as _;
 use bb8_postgres::bb8;
+use bb8_postgres::bb8::PooledConnection;
 use bb8_postgres::PostgresConnectionManager;
+use bytes::BytesMut;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use ipnetwork::IpNetwork;
+use rust_decimal::Decimal;
 use serde_json::Value as JsonValue;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
-use tokio_postgres::NoTls;
-use tokio_postgres::types::Type;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex as AsyncMutex;
+use tokio_postgres::types::{FromSql, IsNull, ToSql, Type};
+use tokio_postgres::{NoTls, Row};
+use crate::errors::{HostErrorCode, HostState};
 use wasmtime::Linker;
-use wasmtime_wasi::p1::WasiP1Ctx;
+
+/// A connection pinned out of the pool by `pg_begin` for the lifetime of a guest-driven
+/// transaction, so `pg_exec_tx`/`pg_query_tx` land on the same backend as the `BEGIN`.
+/// `pg_query_open` reuses the same pinning to back a server-side cursor, recording the
+/// `DECLARE`d cursor's name so `pg_query_fetch` knows to `FETCH` rather than plain `query`.
+struct PinnedTx {
+    conn: PooledConnection<'static, PostgresConnectionManager<NoTls>>,
+    /// Keeps the pool `conn` borrows from alive for as long as this `PinnedTx` is, independent
+    /// of `PostgresHost`'s own lifetime. See the SAFETY comment in `begin()`.
+    pool: Arc<bb8::Pool<PostgresConnectionManager<NoTls>>>,
+    last_used: Instant,
+    cursor_name: Option<String>,
+}
+
+/// A `ToSql` parameter whose value is always NULL, regardless of the column type Postgres
+/// infers for its placeholder. Used to bind `{"t":"null"}` params, which carry no type hint.
+#[derive(Debug)]
+struct SqlNull;
+
+impl ToSql for SqlNull {
+    fn to_sql(&self, _ty: &Type, _out: &mut BytesMut) -> Result<IsNull, Box<dyn std::error::Error + Sync + Send>> {
+        Ok(IsNull::Yes)
+    }
+
+    fn accepts(_ty: &Type) -> bool {
+        true
+    }
+
+    tokio_postgres::types::to_sql_checked!();
+}
+
+/// Decodes the `params` JSON array accepted by `pg_exec_params`/`pg_query_params` into bound
+/// parameters, e.g. `[{"t":"int8","v":42},{"t":"text","v":"x"},{"t":"null"}]`.
+fn decode_params(raw: &[u8]) -> Result<Vec<Box<dyn ToSql + Sync>>, String> {
+    let items: Vec<JsonValue> =
+        serde_json::from_slice(raw).map_err(|e| format!("params is not a JSON array: {e}"))?;
+    items.iter().map(decode_param).collect()
+}
+
+fn decode_param(item: &JsonValue) -> Result<Box<dyn ToSql + Sync>, String> {
+    let obj = item.as_object().ok_or_else(|| "param must be an object".to_string())?;
+    let tag = obj
+        .get("t")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| "param missing \"t\"".to_string())?;
+
+    if tag == "null" {
+        return Ok(Box::new(SqlNull));
+    }
+
+    let value = obj.get("v").ok_or_else(|| format!("param \"{tag}\" missing \"v\""))?;
+    match tag {
+        "int8" => value
+            .as_i64()
+            .map(|v| Box::new(v) as Box<dyn ToSql + Sync>)
+            .ok_or_else(|| "int8 param is not an integer".to_string()),
+        "float8" => value
+            .as_f64()
+            .map(|v| Box::new(v) as Box<dyn ToSql + Sync>)
+            .ok_or_else(|| "float8 param is not a number".to_string()),
+        "text" => value
+            .as_str()
+            .map(|v| Box::new(v.to_string()) as Box<dyn ToSql + Sync>)
+            .ok_or_else(|| "text param is not a string".to_string()),
+        "bool" => value
+            .as_bool()
+            .map(|v| Box::new(v) as Box<dyn ToSql + Sync>)
+            .ok_or_else(|| "bool param is not a boolean".to_string()),
+        "bytea" => {
+            let s = value.as_str().ok_or_else(|| "bytea param is not a string".to_string())?;
+            base64::engine::general_purpose::STANDARD
+                .decode(s)
+                .map(|b| Box::new(b) as Box<dyn ToSql + Sync>)
+                .map_err(|e| format!("bytea param is not valid base64: {e}"))
+        }
+        other => Err(format!("unknown param type \"{other}\"")),
+    }
+}
 
 pub struct PostgresHost {
-    pool: Option<bb8::Pool<PostgresConnectionManager<NoTls>>>,
+    pool: Option<Arc<bb8::Pool<PostgresConnectionManager<NoTls>>>>,
+    transactions: AsyncMutex<HashMap<u64, PinnedTx>>,
+    next_tx_handle: AtomicU64,
 }
 
 impl PostgresHost {
@@ -23,7 +111,7 @@ impl PostgresHost {
             Ok(v) => v,
             Err(_) => {
                 // Postgres is optional and disabled by default.
-                return Self { pool: None };
+                return Self::disabled();
             }
         };
 
@@ -36,7 +124,7 @@ impl PostgresHost {
             Ok(m) => m,
             Err(e) => {
                 eprintln!("Postgres disabled: failed to create connection manager: {e}");
-                return Self { pool: None };
+                return Self::disabled();
             }
         };
 
@@ -44,17 +132,47 @@ impl PostgresHost {
             Ok(p) => p,
             Err(e) => {
                 eprintln!("Postgres disabled: failed to build pool: {e}");
-                return Self { pool: None };
+                return Self::disabled();
             }
         };
 
-        Self { pool: Some(pool) }
+        Self {
+            pool: Some(Arc::new(pool)),
+            transactions: AsyncMutex::new(HashMap::new()),
+            next_tx_handle: AtomicU64::new(1),
+        }
+    }
+
+    fn disabled() -> Self {
+        Self {
+            pool: None,
+            transactions: AsyncMutex::new(HashMap::new()),
+            next_tx_handle: AtomicU64::new(1),
+        }
     }
 
     fn disabled_error() -> &'static str {
         "postgres disabled (POSTGRES_URL or SASSPB_POSTGRES_URL not set)"
     }
 
+    /// Records `msg` into the caller's `HostState::last_error` (see `crate::errors::HostState`),
+    /// mirroring `RedisHost::record_error`.
+    fn record_error(&self, last_error: &crate::errors::LastError, msg: impl Into<String>) {
+        last_error.set(msg);
+    }
+
+    /// The transaction-map lookups (`exec_tx`/`query_tx`/`commit`/`rollback`/`query_fetch`/
+    /// `query_close`) fail with this exact message for a stale or bad handle, distinct from a
+    /// genuine backend failure; pick the matching `HostErrorCode` so a guest can tell "this handle
+    /// is gone, don't retry it" from "the backend call itself failed".
+    fn tx_handle_error_code(e: &str) -> i32 {
+        if e == "unknown transaction handle" {
+            HostErrorCode::NotFound.code()
+        } else {
+            HostErrorCode::BackendError.code()
+        }
+    }
+
     pub async fn exec(&self, sql: String) -> Result<u64, String> {
         let Some(pool) = self.pool.as_ref() else {
             return Err(Self::disabled_error().to_string());
@@ -70,7 +188,34 @@ impl PostgresHost {
             .map_err(|e| e.to_string())
     }
 
+    async fn query_rows(&self, sql: String) -> Result<Vec<Row>, String> {
+        let Some(pool) = self.pool.as_ref() else {
+            return Err(Self::disabled_error().to_string());
+        };
+
+        let conn = pool
+            .get()
+            .await
+            .map_err(|e| format!("bb8 pool error: {e}"))?;
+
+        conn.query(sql.as_str(), &[]).await.map_err(|e| e.to_string())
+    }
+
     pub async fn query_json(&self, sql: String) -> Result<Vec<JsonValue>, String> {
+        let rows = self.query_rows(sql).await?;
+        Ok(rows.iter().map(row_to_json).collect())
+    }
+
+    /// Like `query_json`, but encodes the rows with `encode_binary_rows` instead of
+    /// `serde_json::to_vec`, so the guest can skip JSON parsing on hot numeric-heavy paths.
+    pub async fn query_binary(&self, sql: String) -> Result<Vec<u8>, String> {
+        let rows = self.query_rows(sql).await?;
+        Ok(encode_binary_rows(&rows))
+    }
+
+    /// Like `exec`, but binds `params` server-side via the extended query protocol instead of
+    /// interpolating them into `sql`, so guests no longer have to hand-escape values.
+    pub async fn exec_params(&self, sql: String, params: Vec<Box<dyn ToSql + Sync>>) -> Result<u64, String> {
         let Some(pool) = self.pool.as_ref() else {
             return Err(Self::disabled_error().to_string());
         };
@@ -80,105 +225,495 @@ impl PostgresHost {
             .await
             .map_err(|e| format!("bb8 pool error: {e}"))?;
 
-        let rows = conn.query(sql.as_str(), &[]).await.map_err(|e| e.to_string())?;
-        let mut out: Vec<JsonValue> = Vec::with_capacity(rows.len());
-
-        for row in rows {
-            let mut obj: BTreeMap<String, JsonValue> = BTreeMap::new();
-            for (i, col) in row.columns().iter().enumerate() {
-                let name = col.name().to_string();
-                let ty = col.type_();
-
-                let v = match *ty {
-                    Type::BOOL => row
-                        .try_get::<_, Option<bool>>(i)
-                        .ok()
-                        .flatten()
-                        .map(JsonValue::from)
-                        .unwrap_or(JsonValue::Null),
-
-                    Type::INT2 => row
-                        .try_get::<_, Option<i16>>(i)
-                        .ok()
-                        .flatten()
-                        .map(JsonValue::from)
-                        .unwrap_or(JsonValue::Null),
-                    Type::INT4 => row
-                        .try_get::<_, Option<i32>>(i)
-                        .ok()
-                        .flatten()
-                        .map(JsonValue::from)
-                        .unwrap_or(JsonValue::Null),
-                    Type::INT8 => row
-                        .try_get::<_, Option<i64>>(i)
-                        .ok()
-                        .flatten()
-                        .map(JsonValue::from)
-                        .unwrap_or(JsonValue::Null),
-
-                    Type::FLOAT4 => row
-                        .try_get::<_, Option<f32>>(i)
-                        .ok()
-                        .flatten()
-                        .map(|f| JsonValue::from(f as f64))
-                        .unwrap_or(JsonValue::Null),
-                    Type::FLOAT8 => row
-                        .try_get::<_, Option<f64>>(i)
-                        .ok()
-                        .flatten()
-                        .map(JsonValue::from)
-                        .unwrap_or(JsonValue::Null),
-
-                    Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME => row
-                        .try_get::<_, Option<String>>(i)
-                        .ok()
-                        .flatten()
-                        .map(JsonValue::from)
-                        .unwrap_or(JsonValue::Null),
-
-                    Type::UUID => row
-                        .try_get::<_, Option<uuid::Uuid>>(i)
-                        .ok()
-                        .flatten()
-                        .map(|u| JsonValue::from(u.to_string()))
-                        .unwrap_or(JsonValue::Null),
-
-                    Type::JSON | Type::JSONB => row
-                        .try_get::<_, Option<JsonValue>>(i)
-                        .ok()
-                        .flatten()
-                        .unwrap_or(JsonValue::Null),
-
-                    Type::BYTEA => {
-                        let bytes = row.try_get::<_, Option<Vec<u8>>>(i).ok().flatten();
-                        match bytes {
-                            Some(b) => {
-                                let s = base64::engine::general_purpose::STANDARD.encode(b);
-                                JsonValue::from(s)
-                            }
-                            None => JsonValue::Null,
-                        }
-                    }
+        let refs: Vec<&(dyn ToSql + Sync)> = params.iter().map(|p| p.as_ref()).collect();
+        conn.execute(sql.as_str(), &refs).await.map_err(|e| e.to_string())
+    }
 
-                    _ => row
-                        .try_get::<_, Option<String>>(i)
-                        .ok()
-                        .flatten()
-                        .map(JsonValue::from)
-                        .unwrap_or(JsonValue::Null),
-                };
+    /// Like `query_json`, but binds `params` server-side via the extended query protocol.
+    pub async fn query_params_json(&self, sql: String, params: Vec<Box<dyn ToSql + Sync>>) -> Result<Vec<JsonValue>, String> {
+        let Some(pool) = self.pool.as_ref() else {
+            return Err(Self::disabled_error().to_string());
+        };
+
+        let conn = pool
+            .get()
+            .await
+            .map_err(|e| format!("bb8 pool error: {e}"))?;
+
+        let refs: Vec<&(dyn ToSql + Sync)> = params.iter().map(|p| p.as_ref()).collect();
+        let rows = conn.query(sql.as_str(), &refs).await.map_err(|e| e.to_string())?;
+        Ok(rows.iter().map(row_to_json).collect())
+    }
+
+    /// Checks out a connection, starts a transaction on it, and pins it under a new handle so
+    /// `exec_tx`/`query_tx` land on the same backend until `commit`/`rollback` releases it.
+    pub async fn begin(&self) -> Result<u64, String> {
+        let Some(pool) = self.pool.as_ref() else {
+            return Err(Self::disabled_error().to_string());
+        };
+
+        // Clone the pool's `Arc` so the connection below can borrow from *it* instead of from
+        // `self`: `pool_arc`'s heap allocation outlives `self` as long as `PinnedTx` holds its
+        // own clone, so the pool this `PooledConnection` borrows from stays alive for the full
+        // lifetime of the `PinnedTx`, not just `PostgresHost`'s.
+        let pool_arc = pool.clone();
+        let conn = pool_arc.get().await.map_err(|e| format!("bb8 pool error: {e}"))?;
+
+        // SAFETY: `conn` borrows `*pool_arc`, which lives in the heap allocation behind
+        // `pool_arc`'s `Arc`. `PinnedTx` below stores a clone of that same `Arc`, so the
+        // allocation `conn` points into is kept alive for exactly as long as this `PinnedTx` is,
+        // regardless of whether `self` (`PostgresHost`) has already been dropped in the
+        // meantime — e.g. a guest traps mid-transaction and the process is torn down before the
+        // idle sweeper or `commit`/`rollback` ever runs.
+        let conn: PooledConnection<'static, PostgresConnectionManager<NoTls>> =
+            unsafe { std::mem::transmute(conn) };
+
+        conn.batch_execute("BEGIN").await.map_err(|e| e.to_string())?;
+
+        let handle = self.next_tx_handle.fetch_add(1, Ordering::SeqCst);
+        self.transactions.lock().await.insert(
+            handle,
+            PinnedTx {
+                conn,
+                pool: pool_arc,
+                last_used: Instant::now(),
+                cursor_name: None,
+            },
+        );
+        Ok(handle)
+    }
+
+    pub async fn exec_tx(&self, handle: u64, sql: String) -> Result<u64, String> {
+        let mut txs = self.transactions.lock().await;
+        let Some(tx) = txs.get_mut(&handle) else {
+            return Err("unknown transaction handle".to_string());
+        };
+        tx.last_used = Instant::now();
+        tx.conn.execute(sql.as_str(), &[]).await.map_err(|e| e.to_string())
+    }
+
+    pub async fn query_tx(&self, handle: u64, sql: String) -> Result<Vec<JsonValue>, String> {
+        let mut txs = self.transactions.lock().await;
+        let Some(tx) = txs.get_mut(&handle) else {
+            return Err("unknown transaction handle".to_string());
+        };
+        tx.last_used = Instant::now();
+        let rows = tx.conn.query(sql.as_str(), &[]).await.map_err(|e| e.to_string())?;
+        Ok(rows.iter().map(row_to_json).collect())
+    }
+
+    pub async fn commit(&self, handle: u64) -> Result<(), String> {
+        let Some(tx) = self.transactions.lock().await.remove(&handle) else {
+            return Err("unknown transaction handle".to_string());
+        };
+        tx.conn.batch_execute("COMMIT").await.map_err(|e| e.to_string())
+    }
+
+    pub async fn rollback(&self, handle: u64) -> Result<(), String> {
+        let Some(tx) = self.transactions.lock().await.remove(&handle) else {
+            return Err("unknown transaction handle".to_string());
+        };
+        tx.conn.batch_execute("ROLLBACK").await.map_err(|e| e.to_string())
+    }
+
+    /// Opens a server-side cursor for `sql` on a freshly pinned transaction and returns its
+    /// handle, so a guest can `query_fetch` through an arbitrarily large result set one batch at
+    /// a time instead of needing `out_len` to fit the whole thing. Reuses `begin`'s pinning so
+    /// the cursor and its fetches always land on the same backend connection.
+    pub async fn query_open(&self, sql: String) -> Result<u64, String> {
+        let handle = self.begin().await?;
+        let cursor_name = format!("booster_cursor_{handle}");
 
-                obj.insert(name, v);
+        let mut txs = self.transactions.lock().await;
+        let tx = txs.get_mut(&handle).expect("just inserted by begin()");
+        let declare_sql = format!("DECLARE {cursor_name} CURSOR FOR {sql}");
+        if let Err(e) = tx.conn.batch_execute(&declare_sql).await {
+            let tx = txs.remove(&handle).expect("just inserted by begin()");
+            drop(txs);
+            let _ = tx.conn.batch_execute("ROLLBACK").await;
+            return Err(e.to_string());
+        }
+        tx.cursor_name = Some(cursor_name);
+        Ok(handle)
+    }
+
+    /// Fetches the next `batch` rows from the cursor opened by `query_open`. An empty result
+    /// means the cursor is exhausted; the caller (the `pg_query_fetch` import) turns that into
+    /// the `0` end-of-cursor sentinel.
+    pub async fn query_fetch(&self, handle: u64, batch: i64) -> Result<Vec<JsonValue>, String> {
+        let mut txs = self.transactions.lock().await;
+        let Some(tx) = txs.get_mut(&handle) else {
+            return Err("unknown transaction handle".to_string());
+        };
+        let Some(cursor_name) = tx.cursor_name.clone() else {
+            return Err("handle has no open cursor".to_string());
+        };
+        tx.last_used = Instant::now();
+
+        let fetch_sql = format!("FETCH FORWARD {batch} FROM {cursor_name}");
+        let rows = tx.conn.query(fetch_sql.as_str(), &[]).await.map_err(|e| e.to_string())?;
+        Ok(rows.iter().map(row_to_json).collect())
+    }
+
+    /// Releases the cursor and its pinned connection. A read-only cursor's transaction has
+    /// nothing to commit, so this always rolls back rather than offering a separate commit path.
+    pub async fn query_close(&self, handle: u64) -> Result<(), String> {
+        let Some(tx) = self.transactions.lock().await.remove(&handle) else {
+            return Err("unknown transaction handle".to_string());
+        };
+        tx.conn.batch_execute("ROLLBACK").await.map_err(|e| e.to_string())
+    }
+
+    /// Rolls back and reclaims every pinned transaction that's been idle longer than
+    /// `idle_timeout`, so a guest that traps or forgets to commit can't permanently hold a pool
+    /// slot hostage.
+    async fn reap_idle_transactions(&self, idle_timeout: Duration) {
+        let stale: Vec<u64> = {
+            let txs = self.transactions.lock().await;
+            txs.iter()
+                .filter(|(_, tx)| tx.last_used.elapsed() >= idle_timeout)
+                .map(|(handle, _)| *handle)
+                .collect()
+        };
+
+        for handle in stale {
+            let tx = self.transactions.lock().await.remove(&handle);
+            if let Some(tx) = tx {
+                let _ = tx.conn.batch_execute("ROLLBACK").await;
             }
-            out.push(JsonValue::Object(obj.into_iter().collect()));
         }
+    }
+}
+
+/// Periodically sweeps `pg`'s pinned transactions for ones idle past `BOOSTER_PG_TX_IDLE_MS`
+/// (default 30s), stopping once `pg` itself is dropped.
+fn spawn_tx_idle_sweeper(pg: std::sync::Weak<PostgresHost>) {
+    let idle_timeout = std::env::var("BOOSTER_PG_TX_IDLE_MS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(Duration::from_millis)
+        .unwrap_or(Duration::from_secs(30));
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(5));
+        loop {
+            interval.tick().await;
+            let Some(pg) = pg.upgrade() else {
+                return;
+            };
+            pg.reap_idle_transactions(idle_timeout).await;
+        }
+    });
+}
+
+fn row_to_json(row: &Row) -> JsonValue {
+    let mut obj: BTreeMap<String, JsonValue> = BTreeMap::new();
+    for (i, col) in row.columns().iter().enumerate() {
+        let name = col.name().to_string();
+        let ty = col.type_();
+
+        let v = match *ty {
+            Type::BOOL => row
+                .try_get::<_, Option<bool>>(i)
+                .ok()
+                .flatten()
+                .map(JsonValue::from)
+                .unwrap_or(JsonValue::Null),
+
+            Type::INT2 => row
+                .try_get::<_, Option<i16>>(i)
+                .ok()
+                .flatten()
+                .map(JsonValue::from)
+                .unwrap_or(JsonValue::Null),
+            Type::INT4 => row
+                .try_get::<_, Option<i32>>(i)
+                .ok()
+                .flatten()
+                .map(JsonValue::from)
+                .unwrap_or(JsonValue::Null),
+            Type::INT8 => row
+                .try_get::<_, Option<i64>>(i)
+                .ok()
+                .flatten()
+                .map(JsonValue::from)
+                .unwrap_or(JsonValue::Null),
+
+            Type::FLOAT4 => row
+                .try_get::<_, Option<f32>>(i)
+                .ok()
+                .flatten()
+                .map(|f| JsonValue::from(f as f64))
+                .unwrap_or(JsonValue::Null),
+            Type::FLOAT8 => row
+                .try_get::<_, Option<f64>>(i)
+                .ok()
+                .flatten()
+                .map(JsonValue::from)
+                .unwrap_or(JsonValue::Null),
+
+            Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME => row
+                .try_get::<_, Option<String>>(i)
+                .ok()
+                .flatten()
+                .map(JsonValue::from)
+                .unwrap_or(JsonValue::Null),
+
+            Type::UUID => row
+                .try_get::<_, Option<uuid::Uuid>>(i)
+                .ok()
+                .flatten()
+                .map(|u| JsonValue::from(u.to_string()))
+                .unwrap_or(JsonValue::Null),
+
+            Type::JSON | Type::JSONB => row
+                .try_get::<_, Option<JsonValue>>(i)
+                .ok()
+                .flatten()
+                .unwrap_or(JsonValue::Null),
 
-        Ok(out)
+            Type::BYTEA => {
+                let bytes = row.try_get::<_, Option<Vec<u8>>>(i).ok().flatten();
+                match bytes {
+                    Some(b) => {
+                        let s = base64::engine::general_purpose::STANDARD.encode(b);
+                        JsonValue::from(s)
+                    }
+                    None => JsonValue::Null,
+                }
+            }
+
+            // Serialized as a string rather than f64, since NUMERIC carries more precision
+            // than a JSON number can round-trip.
+            Type::NUMERIC => row
+                .try_get::<_, Option<Decimal>>(i)
+                .ok()
+                .flatten()
+                .map(|d| JsonValue::from(d.to_string()))
+                .unwrap_or(JsonValue::Null),
+
+            Type::TIMESTAMP => row
+                .try_get::<_, Option<NaiveDateTime>>(i)
+                .ok()
+                .flatten()
+                .map(|t| JsonValue::from(t.format("%Y-%m-%dT%H:%M:%S%.f").to_string()))
+                .unwrap_or(JsonValue::Null),
+            Type::TIMESTAMPTZ => row
+                .try_get::<_, Option<DateTime<Utc>>>(i)
+                .ok()
+                .flatten()
+                .map(|t| JsonValue::from(t.to_rfc3339()))
+                .unwrap_or(JsonValue::Null),
+            Type::DATE => row
+                .try_get::<_, Option<NaiveDate>>(i)
+                .ok()
+                .flatten()
+                .map(|d| JsonValue::from(d.to_string()))
+                .unwrap_or(JsonValue::Null),
+            Type::TIME => row
+                .try_get::<_, Option<NaiveTime>>(i)
+                .ok()
+                .flatten()
+                .map(|t| JsonValue::from(t.to_string()))
+                .unwrap_or(JsonValue::Null),
+
+            Type::INET | Type::CIDR => row
+                .try_get::<_, Option<IpNetwork>>(i)
+                .ok()
+                .flatten()
+                .map(|n| JsonValue::from(n.to_string()))
+                .unwrap_or(JsonValue::Null),
+
+            Type::INT4_ARRAY => array_to_json::<i32>(row, i, JsonValue::from),
+            Type::INT8_ARRAY => array_to_json::<i64>(row, i, JsonValue::from),
+            Type::TEXT_ARRAY | Type::VARCHAR_ARRAY => array_to_json::<String>(row, i, JsonValue::from),
+            Type::FLOAT8_ARRAY => array_to_json::<f64>(row, i, JsonValue::from),
+            Type::BOOL_ARRAY => array_to_json::<bool>(row, i, JsonValue::from),
+            Type::UUID_ARRAY => array_to_json::<uuid::Uuid>(row, i, |u| JsonValue::from(u.to_string())),
+
+            _ => row
+                .try_get::<_, Option<String>>(i)
+                .ok()
+                .flatten()
+                .map(JsonValue::from)
+                .unwrap_or(JsonValue::Null),
+        };
+
+        obj.insert(name, v);
+    }
+    JsonValue::Object(obj.into_iter().collect())
+}
+
+/// Decodes an array column (e.g. `INT4_ARRAY`) into a JSON array, preserving per-element nulls.
+/// Falls through to `JsonValue::Null` (rather than panicking) if the column isn't actually that
+/// array type.
+fn array_to_json<T>(row: &Row, i: usize, to_json: impl Fn(T) -> JsonValue) -> JsonValue
+where
+    T: for<'a> FromSql<'a>,
+{
+    row.try_get::<_, Option<Vec<Option<T>>>>(i)
+        .ok()
+        .flatten()
+        .map(|vals| {
+            JsonValue::Array(
+                vals.into_iter()
+                    .map(|v| v.map(&to_json).unwrap_or(JsonValue::Null))
+                    .collect(),
+            )
+        })
+        .unwrap_or(JsonValue::Null)
+}
+
+/// One-byte type tags for `encode_binary_rows`'s column descriptor table, mirroring the `Type`
+/// arms `row_to_json` already handles. `255` is the catch-all, decoded as UTF-8 text like
+/// `row_to_json`'s fallback arm.
+fn binary_type_tag(ty: &Type) -> u8 {
+    match *ty {
+        Type::BOOL => 0,
+        Type::INT2 => 1,
+        Type::INT4 => 2,
+        Type::INT8 => 3,
+        Type::FLOAT4 => 4,
+        Type::FLOAT8 => 5,
+        Type::TEXT | Type::VARCHAR | Type::BPCHAR | Type::NAME => 6,
+        Type::UUID => 7,
+        Type::JSON | Type::JSONB => 8,
+        Type::BYTEA => 9,
+        Type::NUMERIC => 10,
+        Type::TIMESTAMP => 11,
+        Type::TIMESTAMPTZ => 12,
+        Type::DATE => 13,
+        Type::TIME => 14,
+        Type::INET | Type::CIDR => 15,
+        Type::INT4_ARRAY => 16,
+        Type::INT8_ARRAY => 17,
+        Type::TEXT_ARRAY | Type::VARCHAR_ARRAY => 18,
+        Type::FLOAT8_ARRAY => 19,
+        Type::BOOL_ARRAY => 20,
+        Type::UUID_ARRAY => 21,
+        _ => 255,
+    }
+}
+
+/// Array columns are encoded as JSON text rather than a native binary layout, since their
+/// length varies per element; `array_to_json` already does the per-element-null-preserving
+/// decode, so this just reuses it and re-serializes only when the column wasn't NULL.
+fn array_cell_binary<T>(row: &Row, i: usize, to_json: impl Fn(T) -> JsonValue) -> Option<Vec<u8>>
+where
+    T: for<'a> FromSql<'a>,
+{
+    let v = array_to_json(row, i, to_json);
+    if v.is_null() {
+        None
+    } else {
+        serde_json::to_vec(&v).ok()
+    }
+}
+
+/// Encodes a single cell as raw bytes per `tag` (as assigned by `binary_type_tag`): bools as one
+/// `0`/`1` byte, integers and floats as little-endian, UUIDs as their 16 raw bytes, text/bytea as
+/// their raw bytes, JSON as serialized JSON text, everything else as UTF-8 text (or, for arrays,
+/// JSON text). Returns `None` for NULL, which `encode_binary_rows` turns into the `-1` length
+/// sentinel.
+fn cell_to_binary(row: &Row, i: usize, tag: u8) -> Option<Vec<u8>> {
+    match tag {
+        0 => row.try_get::<_, Option<bool>>(i).ok().flatten().map(|v| vec![v as u8]),
+        1 => row.try_get::<_, Option<i16>>(i).ok().flatten().map(|v| v.to_le_bytes().to_vec()),
+        2 => row.try_get::<_, Option<i32>>(i).ok().flatten().map(|v| v.to_le_bytes().to_vec()),
+        3 => row.try_get::<_, Option<i64>>(i).ok().flatten().map(|v| v.to_le_bytes().to_vec()),
+        4 => row.try_get::<_, Option<f32>>(i).ok().flatten().map(|v| v.to_le_bytes().to_vec()),
+        5 => row.try_get::<_, Option<f64>>(i).ok().flatten().map(|v| v.to_le_bytes().to_vec()),
+        6 => row.try_get::<_, Option<String>>(i).ok().flatten().map(|v| v.into_bytes()),
+        7 => row
+            .try_get::<_, Option<uuid::Uuid>>(i)
+            .ok()
+            .flatten()
+            .map(|v| v.as_bytes().to_vec()),
+        8 => row
+            .try_get::<_, Option<JsonValue>>(i)
+            .ok()
+            .flatten()
+            .and_then(|v| serde_json::to_vec(&v).ok()),
+        9 => row.try_get::<_, Option<Vec<u8>>>(i).ok().flatten(),
+        10 => row
+            .try_get::<_, Option<Decimal>>(i)
+            .ok()
+            .flatten()
+            .map(|v| v.to_string().into_bytes()),
+        11 => row
+            .try_get::<_, Option<NaiveDateTime>>(i)
+            .ok()
+            .flatten()
+            .map(|v| v.format("%Y-%m-%dT%H:%M:%S%.f").to_string().into_bytes()),
+        12 => row
+            .try_get::<_, Option<DateTime<Utc>>>(i)
+            .ok()
+            .flatten()
+            .map(|v| v.to_rfc3339().into_bytes()),
+        13 => row
+            .try_get::<_, Option<NaiveDate>>(i)
+            .ok()
+            .flatten()
+            .map(|v| v.to_string().into_bytes()),
+        14 => row
+            .try_get::<_, Option<NaiveTime>>(i)
+            .ok()
+            .flatten()
+            .map(|v| v.to_string().into_bytes()),
+        15 => row
+            .try_get::<_, Option<IpNetwork>>(i)
+            .ok()
+            .flatten()
+            .map(|v| v.to_string().into_bytes()),
+        16 => array_cell_binary::<i32>(row, i, JsonValue::from),
+        17 => array_cell_binary::<i64>(row, i, JsonValue::from),
+        18 => array_cell_binary::<String>(row, i, JsonValue::from),
+        19 => array_cell_binary::<f64>(row, i, JsonValue::from),
+        20 => array_cell_binary::<bool>(row, i, JsonValue::from),
+        21 => array_cell_binary::<uuid::Uuid>(row, i, |u| JsonValue::from(u.to_string())),
+        _ => row.try_get::<_, Option<String>>(i).ok().flatten().map(|v| v.into_bytes()),
+    }
+}
+
+/// Encodes `rows` into the compact binary format returned by `pg_query_binary`: a header of
+/// `[i32 row_count][i32 col_count]`, a column descriptor table of
+/// `[i32 name_len][name bytes][u8 type_tag]` per column (taken from the first row, since a
+/// single query's rows always share a shape), then each row as `[i32 len][bytes]` cells with
+/// `-1` as the NULL sentinel.
+fn encode_binary_rows(rows: &[Row]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let row_count = rows.len() as i32;
+    let columns: &[tokio_postgres::Column] = rows.first().map(|r| r.columns()).unwrap_or(&[]);
+    let col_count = columns.len() as i32;
+
+    out.extend_from_slice(&row_count.to_le_bytes());
+    out.extend_from_slice(&col_count.to_le_bytes());
+
+    let tags: Vec<u8> = columns.iter().map(|c| binary_type_tag(c.type_())).collect();
+    for (col, tag) in columns.iter().zip(&tags) {
+        let name = col.name().as_bytes();
+        out.extend_from_slice(&(name.len() as i32).to_le_bytes());
+        out.extend_from_slice(name);
+        out.push(*tag);
+    }
+
+    for row in rows {
+        for (i, tag) in tags.iter().enumerate() {
+            match cell_to_binary(row, i, *tag) {
+                Some(bytes) => {
+                    out.extend_from_slice(&(bytes.len() as i32).to_le_bytes());
+                    out.extend_from_slice(&bytes);
+                }
+                None => out.extend_from_slice(&(-1i32).to_le_bytes()),
+            }
+        }
     }
+
+    out
 }
 
 fn read_guest_bytes(
-    caller: &mut wasmtime::Caller<'_, WasiP1Ctx>,
+    caller: &mut wasmtime::Caller<'_, HostState>,
     ptr: i32,
     len: i32,
 ) -> Result<Vec<u8>, ()> {
@@ -194,7 +729,7 @@ fn read_guest_bytes(
 }
 
 fn write_guest_bytes(
-    caller: &mut wasmtime::Caller<'_, WasiP1Ctx>,
+    caller: &mut wasmtime::Caller<'_, HostState>,
     ptr: i32,
     data: &[u8],
 ) -> Result<(), ()> {
@@ -212,7 +747,7 @@ fn write_guest_bytes(
 mod postgres_tests {
     use super::*;
     use wasmtime::{Engine, Linker, Module, Store};
-    use wasmtime_wasi::{WasiCtx, p1::WasiP1Ctx, p2::pipe::MemoryOutputPipe};
+    use wasmtime_wasi::{WasiCtx, p2::pipe::MemoryOutputPipe};
 
     #[tokio::test]
     async fn test_postgres_host_imports_roundtrip() {
@@ -250,8 +785,8 @@ mod postgres_tests {
         config.async_support(true);
         let engine = Engine::new(&config).expect("engine");
 
-        let mut linker: Linker<WasiP1Ctx> = Linker::new(&engine);
-        wasmtime_wasi::p1::add_to_linker_async(&mut linker, |cx| cx).expect("add wasi");
+        let mut linker: Linker<HostState> = Linker::new(&engine);
+        wasmtime_wasi::p1::add_to_linker_async(&mut linker, |state: &mut HostState| &mut state.wasi).expect("add wasi");
         add_postgres_to_linker(&mut linker, pg.clone()).expect("add postgres");
 
         // Layout:
@@ -311,8 +846,8 @@ mod postgres_tests {
             .stderr(stderr_pipe)
             .build_p1();
 
-        let mut store: Store<WasiP1Ctx> = Store::new(&engine, WasiCtx::builder().build_p1());
-        *store.data_mut() = wasi;
+        let mut store: Store<HostState> = Store::new(&engine, HostState::new(WasiCtx::builder().build_p1()));
+        store.data_mut().wasi = wasi;
 
         let instance = linker
             .instantiate_async(&mut store, &module)
@@ -347,74 +882,588 @@ mod postgres_tests {
             .unwrap_or("");
         assert_eq!(got, value);
     }
+
+    fn postgres_test_enabled() -> bool {
+        std::env::var("BOOSTER_TEST_POSTGRES")
+            .ok()
+            .as_deref()
+            .map(|v| matches!(v, "1" | "true" | "TRUE" | "yes" | "YES"))
+            .unwrap_or(false)
+    }
+
+    #[tokio::test]
+    async fn test_begin_exec_tx_commit_roundtrip() {
+        if !postgres_test_enabled() {
+            return;
+        }
+        if std::env::var("POSTGRES_URL").is_err() && std::env::var("SASSPB_POSTGRES_URL").is_err() {
+            eprintln!("skipping postgres test: POSTGRES_URL/SASSPB_POSTGRES_URL not set");
+            return;
+        }
+
+        let pg = PostgresHost::new_from_env().await;
+        if pg.pool.is_none() {
+            panic!("Postgres expected enabled for test (set BOOSTER_TEST_POSTGRES=1 and ensure POSTGRES_URL/SASSPB_POSTGRES_URL is reachable)");
+        }
+
+        // An await point between `begin` and `exec_tx` exercises the case the `transmute` SAFETY
+        // comment in `begin()` is about: the pinned connection must keep living even if other
+        // tasks run (and could in principle drop `pg`) in between.
+        let handle = pg.begin().await.expect("begin");
+        tokio::task::yield_now().await;
+        let n = pg.exec_tx(handle, "SELECT 1".to_owned()).await.expect("exec_tx");
+        assert_eq!(n, 0);
+        pg.commit(handle).await.expect("commit");
+
+        assert!(pg.exec_tx(handle, "SELECT 1".to_owned()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_query_open_fetch_close_cursor_roundtrip() {
+        if !postgres_test_enabled() {
+            return;
+        }
+        if std::env::var("POSTGRES_URL").is_err() && std::env::var("SASSPB_POSTGRES_URL").is_err() {
+            eprintln!("skipping postgres test: POSTGRES_URL/SASSPB_POSTGRES_URL not set");
+            return;
+        }
+
+        let pg = PostgresHost::new_from_env().await;
+        if pg.pool.is_none() {
+            panic!("Postgres expected enabled for test (set BOOSTER_TEST_POSTGRES=1 and ensure POSTGRES_URL/SASSPB_POSTGRES_URL is reachable)");
+        }
+
+        let handle = pg
+            .query_open("SELECT generate_series(1, 5) AS n".to_owned())
+            .await
+            .expect("query_open");
+
+        let first_batch = pg.query_fetch(handle, 3).await.expect("query_fetch 1");
+        assert_eq!(first_batch.len(), 3);
+
+        let second_batch = pg.query_fetch(handle, 3).await.expect("query_fetch 2");
+        assert_eq!(second_batch.len(), 2);
+
+        let exhausted = pg.query_fetch(handle, 3).await.expect("query_fetch 3");
+        assert!(exhausted.is_empty());
+
+        pg.query_close(handle).await.expect("query_close");
+        assert!(pg.query_fetch(handle, 1).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_query_binary_encodes_header_and_cells() {
+        if !postgres_test_enabled() {
+            return;
+        }
+        if std::env::var("POSTGRES_URL").is_err() && std::env::var("SASSPB_POSTGRES_URL").is_err() {
+            eprintln!("skipping postgres test: POSTGRES_URL/SASSPB_POSTGRES_URL not set");
+            return;
+        }
+
+        let pg = PostgresHost::new_from_env().await;
+        if pg.pool.is_none() {
+            panic!("Postgres expected enabled for test (set BOOSTER_TEST_POSTGRES=1 and ensure POSTGRES_URL/SASSPB_POSTGRES_URL is reachable)");
+        }
+
+        let bytes = pg
+            .query_binary("SELECT 1::int8 AS n, 'hi'::text AS s".to_owned())
+            .await
+            .expect("query_binary");
+
+        let row_count = i32::from_le_bytes(bytes[0..4].try_into().unwrap());
+        let col_count = i32::from_le_bytes(bytes[4..8].try_into().unwrap());
+        assert_eq!(row_count, 1);
+        assert_eq!(col_count, 2);
+
+        let mut pos = 8;
+        let name_len = i32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap()) as usize;
+        pos += 4;
+        assert_eq!(&bytes[pos..pos + name_len], b"n");
+        pos += name_len;
+        assert_eq!(bytes[pos], 3); // int8 tag
+        pos += 1;
+
+        let name_len = i32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap()) as usize;
+        pos += 4;
+        assert_eq!(&bytes[pos..pos + name_len], b"s");
+        pos += name_len;
+        assert_eq!(bytes[pos], 6); // text tag
+        pos += 1;
+
+        let n_len = i32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap()) as usize;
+        pos += 4;
+        let n_val = i64::from_le_bytes(bytes[pos..pos + n_len].try_into().unwrap());
+        assert_eq!(n_val, 1);
+        pos += n_len;
+
+        let s_len = i32::from_le_bytes(bytes[pos..pos + 4].try_into().unwrap()) as usize;
+        pos += 4;
+        assert_eq!(&bytes[pos..pos + s_len], b"hi");
+    }
 }
 
-pub fn add_postgres_to_linker(linker: &mut Linker<WasiP1Ctx>, pg: Arc<PostgresHost>) -> Result<(), Error> {
+pub fn add_postgres_to_linker(linker: &mut Linker<HostState>, pg: Arc<PostgresHost>) -> Result<(), Error> {
     let pg_exec_host = pg.clone();
     linker.func_wrap_async(
         "bosbase_postgres",
         "pg_exec",
-        move |mut caller: wasmtime::Caller<'_, WasiP1Ctx>, (sptr, slen): (i32, i32)| {
+        move |mut caller: wasmtime::Caller<'_, HostState>, (sptr, slen): (i32, i32)| {
             let pg = pg_exec_host.clone();
             Box::new(async move {
+                let last_error = caller.data().last_error.clone();
                 let sql_bytes = match read_guest_bytes(&mut caller, sptr, slen) {
                     Ok(b) => b,
-                    Err(_) => return Ok(-2),
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
                 };
                 let sql = match String::from_utf8(sql_bytes) {
                     Ok(s) => s,
-                    Err(_) => return Ok(-2),
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
                 };
 
                 match pg.exec(sql).await {
                     Ok(n) => Ok((n.min(i32::MAX as u64)) as i32),
-                    Err(_) => Ok(-1),
+                    Err(e) => {
+                        pg.record_error(&last_error, format!("pg_exec: {e}"));
+                        Ok(HostErrorCode::BackendError.code())
+                    }
                 }
             })
         },
     )?;
 
-    let pg_query_host = pg;
+    let pg_query_host = pg.clone();
     linker.func_wrap_async(
         "bosbase_postgres",
         "pg_query",
-        move |mut caller: wasmtime::Caller<'_, WasiP1Ctx>,
+        move |mut caller: wasmtime::Caller<'_, HostState>,
               (sptr, slen, out_ptr, out_len): (i32, i32, i32, i32)| {
             let pg = pg_query_host.clone();
             Box::new(async move {
+                let last_error = caller.data().last_error.clone();
                 if out_len < 0 {
-                    return Ok(-3);
+                    return Ok(HostErrorCode::BadArgs.code());
                 }
 
                 let sql_bytes = match read_guest_bytes(&mut caller, sptr, slen) {
                     Ok(b) => b,
-                    Err(_) => return Ok(-3),
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
                 };
                 let sql = match String::from_utf8(sql_bytes) {
                     Ok(s) => s,
-                    Err(_) => return Ok(-3),
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
                 };
 
                 let rows = match pg.query_json(sql).await {
                     Ok(r) => r,
-                    Err(_) => return Ok(-1),
+                    Err(e) => {
+                        pg.record_error(&last_error, format!("pg_query: {e}"));
+                        return Ok(HostErrorCode::BackendError.code());
+                    }
                 };
 
                 let payload = match serde_json::to_vec(&rows) {
                     Ok(v) => v,
-                    Err(_) => return Ok(-1),
+                    Err(e) => {
+                        pg.record_error(&last_error, format!("pg_query: failed to encode rows as json: {e}"));
+                        return Ok(HostErrorCode::BackendError.code());
+                    }
                 };
 
                 if (payload.len() as i32) > out_len {
-                    return Ok(-2);
+                    return Ok(HostErrorCode::Truncated.code());
                 }
                 if write_guest_bytes(&mut caller, out_ptr, &payload).is_err() {
-                    return Ok(-3);
+                    return Ok(HostErrorCode::BadArgs.code());
                 }
                 Ok(payload.len() as i32)
             })
         },
     )?;
 
+    let pg_exec_params_host = pg.clone();
+    linker.func_wrap_async(
+        "bosbase_postgres",
+        "pg_exec_params",
+        move |mut caller: wasmtime::Caller<'_, HostState>,
+              (sptr, slen, pptr, plen): (i32, i32, i32, i32)| {
+            let pg = pg_exec_params_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                let sql_bytes = match read_guest_bytes(&mut caller, sptr, slen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let sql = match String::from_utf8(sql_bytes) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let params_bytes = match read_guest_bytes(&mut caller, pptr, plen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let params = match decode_params(&params_bytes) {
+                    Ok(p) => p,
+                    Err(e) => {
+                        pg.record_error(&last_error, format!("pg_exec_params: bad param encoding: {e}"));
+                        return Ok(HostErrorCode::BadArgs.code());
+                    }
+                };
+
+                match pg.exec_params(sql, params).await {
+                    Ok(n) => Ok((n.min(i32::MAX as u64)) as i32),
+                    Err(e) => {
+                        pg.record_error(&last_error, format!("pg_exec_params: {e}"));
+                        Ok(HostErrorCode::BackendError.code())
+                    }
+                }
+            })
+        },
+    )?;
+
+    let pg_query_params_host = pg.clone();
+    linker.func_wrap_async(
+        "bosbase_postgres",
+        "pg_query_params",
+        move |mut caller: wasmtime::Caller<'_, HostState>,
+              (sptr, slen, pptr, plen, out_ptr, out_len): (i32, i32, i32, i32, i32, i32)| {
+            let pg = pg_query_params_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                if out_len < 0 {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+
+                let sql_bytes = match read_guest_bytes(&mut caller, sptr, slen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let sql = match String::from_utf8(sql_bytes) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let params_bytes = match read_guest_bytes(&mut caller, pptr, plen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let params = match decode_params(&params_bytes) {
+                    Ok(p) => p,
+                    Err(e) => {
+                        pg.record_error(&last_error, format!("pg_query_params: bad param encoding: {e}"));
+                        return Ok(HostErrorCode::BadArgs.code());
+                    }
+                };
+
+                let rows = match pg.query_params_json(sql, params).await {
+                    Ok(r) => r,
+                    Err(e) => {
+                        pg.record_error(&last_error, format!("pg_query_params: {e}"));
+                        return Ok(HostErrorCode::BackendError.code());
+                    }
+                };
+
+                let payload = match serde_json::to_vec(&rows) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        pg.record_error(&last_error, format!("pg_query_params: failed to encode rows as json: {e}"));
+                        return Ok(HostErrorCode::BackendError.code());
+                    }
+                };
+
+                if (payload.len() as i32) > out_len {
+                    return Ok(HostErrorCode::Truncated.code());
+                }
+                if write_guest_bytes(&mut caller, out_ptr, &payload).is_err() {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+                Ok(payload.len() as i32)
+            })
+        },
+    )?;
+
+    let pg_query_binary_host = pg.clone();
+    linker.func_wrap_async(
+        "bosbase_postgres",
+        "pg_query_binary",
+        move |mut caller: wasmtime::Caller<'_, HostState>,
+              (sptr, slen, out_ptr, out_len): (i32, i32, i32, i32)| {
+            let pg = pg_query_binary_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                if out_len < 0 {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+
+                let sql_bytes = match read_guest_bytes(&mut caller, sptr, slen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let sql = match String::from_utf8(sql_bytes) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+
+                let payload = match pg.query_binary(sql).await {
+                    Ok(p) => p,
+                    Err(e) => {
+                        pg.record_error(&last_error, format!("pg_query_binary: {e}"));
+                        return Ok(HostErrorCode::BackendError.code());
+                    }
+                };
+
+                if (payload.len() as i32) > out_len {
+                    return Ok(HostErrorCode::Truncated.code());
+                }
+                if write_guest_bytes(&mut caller, out_ptr, &payload).is_err() {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+                Ok(payload.len() as i32)
+            })
+        },
+    )?;
+
+    spawn_tx_idle_sweeper(Arc::downgrade(&pg));
+
+    let pg_begin_host = pg.clone();
+    linker.func_wrap_async(
+        "bosbase_postgres",
+        "pg_begin",
+        move |caller: wasmtime::Caller<'_, HostState>, (): ()| {
+            let pg = pg_begin_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                match pg.begin().await {
+                    Ok(handle) => Ok(handle as i64),
+                    Err(e) => {
+                        pg.record_error(&last_error, format!("pg_begin: {e}"));
+                        Ok(HostErrorCode::BackendError.code() as i64)
+                    }
+                }
+            })
+        },
+    )?;
+
+    let pg_exec_tx_host = pg.clone();
+    linker.func_wrap_async(
+        "bosbase_postgres",
+        "pg_exec_tx",
+        move |mut caller: wasmtime::Caller<'_, HostState>, (handle, sptr, slen): (i64, i32, i32)| {
+            let pg = pg_exec_tx_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                if handle < 0 {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+                let sql_bytes = match read_guest_bytes(&mut caller, sptr, slen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let sql = match String::from_utf8(sql_bytes) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+
+                match pg.exec_tx(handle as u64, sql).await {
+                    Ok(n) => Ok((n.min(i32::MAX as u64)) as i32),
+                    Err(e) => {
+                        let code = PostgresHost::tx_handle_error_code(&e);
+                        pg.record_error(&last_error, format!("pg_exec_tx: {e}"));
+                        Ok(code)
+                    }
+                }
+            })
+        },
+    )?;
+
+    let pg_query_tx_host = pg.clone();
+    linker.func_wrap_async(
+        "bosbase_postgres",
+        "pg_query_tx",
+        move |mut caller: wasmtime::Caller<'_, HostState>,
+              (handle, sptr, slen, out_ptr, out_len): (i64, i32, i32, i32, i32)| {
+            let pg = pg_query_tx_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                if handle < 0 || out_len < 0 {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+                let sql_bytes = match read_guest_bytes(&mut caller, sptr, slen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let sql = match String::from_utf8(sql_bytes) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+
+                let rows = match pg.query_tx(handle as u64, sql).await {
+                    Ok(r) => r,
+                    Err(e) => {
+                        let code = PostgresHost::tx_handle_error_code(&e);
+                        pg.record_error(&last_error, format!("pg_query_tx: {e}"));
+                        return Ok(code);
+                    }
+                };
+
+                let payload = match serde_json::to_vec(&rows) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        pg.record_error(&last_error, format!("pg_query_tx: failed to encode rows as json: {e}"));
+                        return Ok(HostErrorCode::BackendError.code());
+                    }
+                };
+
+                if (payload.len() as i32) > out_len {
+                    return Ok(HostErrorCode::Truncated.code());
+                }
+                if write_guest_bytes(&mut caller, out_ptr, &payload).is_err() {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+                Ok(payload.len() as i32)
+            })
+        },
+    )?;
+
+    let pg_commit_host = pg.clone();
+    linker.func_wrap_async(
+        "bosbase_postgres",
+        "pg_commit",
+        move |caller: wasmtime::Caller<'_, HostState>, (handle,): (i64,)| {
+            let pg = pg_commit_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                if handle < 0 {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+                match pg.commit(handle as u64).await {
+                    Ok(()) => Ok(HostErrorCode::Ok.code()),
+                    Err(e) => {
+                        let code = PostgresHost::tx_handle_error_code(&e);
+                        pg.record_error(&last_error, format!("pg_commit: {e}"));
+                        Ok(code)
+                    }
+                }
+            })
+        },
+    )?;
+
+    let pg_rollback_host = pg.clone();
+    linker.func_wrap_async(
+        "bosbase_postgres",
+        "pg_rollback",
+        move |caller: wasmtime::Caller<'_, HostState>, (handle,): (i64,)| {
+            let pg = pg_rollback_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                if handle < 0 {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+                match pg.rollback(handle as u64).await {
+                    Ok(()) => Ok(HostErrorCode::Ok.code()),
+                    Err(e) => {
+                        let code = PostgresHost::tx_handle_error_code(&e);
+                        pg.record_error(&last_error, format!("pg_rollback: {e}"));
+                        Ok(code)
+                    }
+                }
+            })
+        },
+    )?;
+
+    let pg_query_open_host = pg.clone();
+    linker.func_wrap_async(
+        "bosbase_postgres",
+        "pg_query_open",
+        move |mut caller: wasmtime::Caller<'_, HostState>, (sptr, slen): (i32, i32)| {
+            let pg = pg_query_open_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                let sql_bytes = match read_guest_bytes(&mut caller, sptr, slen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code() as i64),
+                };
+                let sql = match String::from_utf8(sql_bytes) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code() as i64),
+                };
+
+                match pg.query_open(sql).await {
+                    Ok(handle) => Ok(handle as i64),
+                    Err(e) => {
+                        pg.record_error(&last_error, format!("pg_query_open: {e}"));
+                        Ok(HostErrorCode::BackendError.code() as i64)
+                    }
+                }
+            })
+        },
+    )?;
+
+    let pg_query_fetch_host = pg.clone();
+    linker.func_wrap_async(
+        "bosbase_postgres",
+        "pg_query_fetch",
+        move |mut caller: wasmtime::Caller<'_, HostState>,
+              (handle, batch, out_ptr, out_len): (i64, i64, i32, i32)| {
+            let pg = pg_query_fetch_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                if handle < 0 || batch <= 0 || out_len < 0 {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+
+                let rows = match pg.query_fetch(handle as u64, batch).await {
+                    Ok(r) => r,
+                    Err(e) => {
+                        let code = PostgresHost::tx_handle_error_code(&e);
+                        pg.record_error(&last_error, format!("pg_query_fetch: {e}"));
+                        return Ok(code);
+                    }
+                };
+                if rows.is_empty() {
+                    return Ok(HostErrorCode::Ok.code());
+                }
+
+                let payload = match serde_json::to_vec(&rows) {
+                    Ok(v) => v,
+                    Err(e) => {
+                        pg.record_error(&last_error, format!("pg_query_fetch: failed to encode rows as json: {e}"));
+                        return Ok(HostErrorCode::BackendError.code());
+                    }
+                };
+
+                if (payload.len() as i32) > out_len {
+                    return Ok(HostErrorCode::Truncated.code());
+                }
+                if write_guest_bytes(&mut caller, out_ptr, &payload).is_err() {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+                Ok(payload.len() as i32)
+            })
+        },
+    )?;
+
+    let pg_query_close_host = pg;
+    linker.func_wrap_async(
+        "bosbase_postgres",
+        "pg_query_close",
+        move |caller: wasmtime::Caller<'_, HostState>, (handle,): (i64,)| {
+            let pg = pg_query_close_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                if handle < 0 {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+                match pg.query_close(handle as u64).await {
+                    Ok(()) => Ok(HostErrorCode::Ok.code()),
+                    Err(e) => {
+                        let code = PostgresHost::tx_handle_error_code(&e);
+                        pg.record_error(&last_error, format!("pg_query_close: {e}"));
+                        Ok(code)
+                    }
+                }
+            })
+        },
+    )?;
+
     Ok(())
 }
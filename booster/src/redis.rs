@@ -1,49 +1,317 @@
 use anyhow::Error;
-use std::sync::Arc;
+use chrono::{NaiveDateTime, Utc};
+use crate::errors::{HostErrorCode, HostState};
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Weak};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Mutex as AsyncMutex, RwLock as AsyncRwLock};
 use wasmtime::Linker;
-use wasmtime_wasi::p1::WasiP1Ctx;
+
+/// A live subscription to a Redis pub/sub channel. Messages are forwarded from the
+/// dedicated `PubSub` connection into `rx` by `listen_task` so that `redis_poll` can
+/// drain them non-blockingly. Reclaimed either explicitly via `unsubscribe` or by the idle
+/// sweeper once `last_polled` is stale, so a guest that traps before unsubscribing can't
+/// permanently leak the dedicated `PubSub` connection and its listener task.
+struct Subscription {
+    rx: mpsc::Receiver<Vec<u8>>,
+    listen_task: tokio::task::JoinHandle<()>,
+    last_polled: Instant,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        self.listen_task.abort();
+    }
+}
+
+/// Size of the scratch buffer `StagedValue` reuses across `redis_read_chunk` calls, mirroring
+/// the window a high-throughput Redis consumer would cap a single read at.
+const STAGE_SCRATCH_BYTES: usize = 8 * 1024;
+
+/// A value staged by `redis_get_len` for windowed retrieval via `redis_read_chunk`, so a guest
+/// can stream an oversized value through a fixed-size buffer instead of needing to guess a
+/// single `out_len` large enough to hold it in one call. Removed once a read reaches the end of
+/// `data`, or (a guest that calls `redis_get_len` and never drains it, or traps mid-read) by the
+/// idle sweeper once `last_read` is stale.
+struct StagedValue {
+    data: Vec<u8>,
+    scratch: Vec<u8>,
+    last_read: Instant,
+}
+
+impl StagedValue {
+    fn new(data: Vec<u8>) -> Self {
+        Self {
+            data,
+            scratch: Vec::with_capacity(STAGE_SCRATCH_BYTES),
+            last_read: Instant::now(),
+        }
+    }
+
+    /// Refills the reusable scratch buffer with up to `max_len` bytes (itself capped to
+    /// `STAGE_SCRATCH_BYTES`, the buffer's reserved capacity) starting at `offset`, copying the
+    /// window forward in place rather than allocating a fresh `Vec` on every call.
+    fn read_into_scratch(&mut self, offset: usize, max_len: usize) -> &[u8] {
+        self.scratch.clear();
+        if offset < self.data.len() {
+            let take = max_len.min(STAGE_SCRATCH_BYTES);
+            let end = (offset + take).min(self.data.len());
+            self.scratch.extend_from_slice(&self.data[offset..end]);
+        }
+        &self.scratch
+    }
+}
+
+type BackendResult<T> = Result<T, bb8_redis::redis::RedisError>;
+type BackendFuture<'a, T> = Pin<Box<dyn Future<Output = BackendResult<T>> + Send + 'a>>;
+
+fn backend_error(msg: impl Into<String>) -> bb8_redis::redis::RedisError {
+    bb8_redis::redis::RedisError::from((
+        bb8_redis::redis::ErrorKind::Io,
+        "cache backend error",
+        msg.into(),
+    ))
+}
+
+/// Key/value surface shared by the Redis-backed pool and the in-memory fallback, so guest
+/// code written against `get`/`set`/`set_ex`/`exists`/`del`/`invalidate` behaves the same way
+/// whether `REDIS_URL` is configured (prod) or absent (dev).
+trait CacheBackend: Send + Sync {
+    fn get<'a>(&'a self, key: String) -> BackendFuture<'a, Option<Vec<u8>>>;
+    fn set<'a>(&'a self, key: String, val: Vec<u8>) -> BackendFuture<'a, ()>;
+    fn set_ex<'a>(&'a self, key: String, val: Vec<u8>, ttl_seconds: u64) -> BackendFuture<'a, ()>;
+    fn exists<'a>(&'a self, key: String) -> BackendFuture<'a, bool>;
+    fn del<'a>(&'a self, key: String) -> BackendFuture<'a, u64>;
+    fn invalidate<'a>(&'a self, pattern: String) -> BackendFuture<'a, u64>;
+}
+
+struct RedisBackend {
+    pool: bb8_redis::bb8::Pool<bb8_redis::RedisConnectionManager>,
+}
+
+impl CacheBackend for RedisBackend {
+    fn get<'a>(&'a self, key: String) -> BackendFuture<'a, Option<Vec<u8>>> {
+        use bb8_redis::redis::AsyncCommands;
+        Box::pin(async move {
+            let mut conn = self.pool.get().await.map_err(|e| backend_error(e.to_string()))?;
+            conn.get(key).await
+        })
+    }
+
+    fn set<'a>(&'a self, key: String, val: Vec<u8>) -> BackendFuture<'a, ()> {
+        use bb8_redis::redis::AsyncCommands;
+        Box::pin(async move {
+            let mut conn = self.pool.get().await.map_err(|e| backend_error(e.to_string()))?;
+            conn.set(key, val).await
+        })
+    }
+
+    fn set_ex<'a>(&'a self, key: String, val: Vec<u8>, ttl_seconds: u64) -> BackendFuture<'a, ()> {
+        use bb8_redis::redis::AsyncCommands;
+        Box::pin(async move {
+            let mut conn = self.pool.get().await.map_err(|e| backend_error(e.to_string()))?;
+            conn.set_ex(key, val, ttl_seconds).await
+        })
+    }
+
+    fn exists<'a>(&'a self, key: String) -> BackendFuture<'a, bool> {
+        use bb8_redis::redis::AsyncCommands;
+        Box::pin(async move {
+            let mut conn = self.pool.get().await.map_err(|e| backend_error(e.to_string()))?;
+            conn.exists(key).await
+        })
+    }
+
+    fn del<'a>(&'a self, key: String) -> BackendFuture<'a, u64> {
+        use bb8_redis::redis::AsyncCommands;
+        Box::pin(async move {
+            let mut conn = self.pool.get().await.map_err(|e| backend_error(e.to_string()))?;
+            conn.del(key).await
+        })
+    }
+
+    fn invalidate<'a>(&'a self, pattern: String) -> BackendFuture<'a, u64> {
+        use bb8_redis::redis::AsyncCommands;
+        Box::pin(async move {
+            let mut conn = self.pool.get().await.map_err(|e| backend_error(e.to_string()))?;
+            let keys: Vec<String> = conn.keys(pattern).await?;
+            if keys.is_empty() {
+                return Ok(0);
+            }
+            conn.del(keys).await
+        })
+    }
+}
+
+/// A single cached value. `expires_at` is `None` for entries written via `set` (no TTL).
+struct CacheEntry {
+    expires_at: Option<NaiveDateTime>,
+    payload: Vec<u8>,
+}
+
+/// Entirely in-process key/value store used when `REDIS_URL` is unset (or
+/// `BOOSTER_CACHE_FORCE_MEMORY` forces it), so guests that only need caching keep working
+/// without a Redis instance. Expired entries are evicted lazily on read.
+struct MemoryBackend {
+    store: AsyncRwLock<HashMap<String, CacheEntry>>,
+}
+
+impl MemoryBackend {
+    fn new() -> Self {
+        Self {
+            store: AsyncRwLock::new(HashMap::new()),
+        }
+    }
+
+    fn is_live(entry: &CacheEntry, now: NaiveDateTime) -> bool {
+        entry.expires_at.map(|exp| exp > now).unwrap_or(true)
+    }
+}
+
+impl CacheBackend for MemoryBackend {
+    fn get<'a>(&'a self, key: String) -> BackendFuture<'a, Option<Vec<u8>>> {
+        Box::pin(async move {
+            let now = Utc::now().naive_utc();
+            let mut store = self.store.write().await;
+            if store.get(&key).is_some_and(|e| !Self::is_live(e, now)) {
+                store.remove(&key);
+            }
+            Ok(store.get(&key).map(|e| e.payload.clone()))
+        })
+    }
+
+    fn set<'a>(&'a self, key: String, val: Vec<u8>) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            self.store.write().await.insert(
+                key,
+                CacheEntry {
+                    expires_at: None,
+                    payload: val,
+                },
+            );
+            Ok(())
+        })
+    }
+
+    fn set_ex<'a>(&'a self, key: String, val: Vec<u8>, ttl_seconds: u64) -> BackendFuture<'a, ()> {
+        Box::pin(async move {
+            let expires_at = Utc::now().naive_utc() + chrono::Duration::seconds(ttl_seconds as i64);
+            self.store.write().await.insert(
+                key,
+                CacheEntry {
+                    expires_at: Some(expires_at),
+                    payload: val,
+                },
+            );
+            Ok(())
+        })
+    }
+
+    fn exists<'a>(&'a self, key: String) -> BackendFuture<'a, bool> {
+        Box::pin(async move {
+            let now = Utc::now().naive_utc();
+            let mut store = self.store.write().await;
+            if store.get(&key).is_some_and(|e| !Self::is_live(e, now)) {
+                store.remove(&key);
+            }
+            Ok(store.contains_key(&key))
+        })
+    }
+
+    fn del<'a>(&'a self, key: String) -> BackendFuture<'a, u64> {
+        Box::pin(async move {
+            let removed = self.store.write().await.remove(&key).is_some();
+            Ok(removed as u64)
+        })
+    }
+
+    fn invalidate<'a>(&'a self, pattern: String) -> BackendFuture<'a, u64> {
+        Box::pin(async move {
+            let mut store = self.store.write().await;
+            if pattern == "*" {
+                let n = store.len() as u64;
+                store.clear();
+                return Ok(n);
+            }
+            if let Some(prefix) = pattern.strip_suffix('*') {
+                let before = store.len();
+                store.retain(|k, _| !k.starts_with(prefix));
+                return Ok((before - store.len()) as u64);
+            }
+            Ok(store.remove(&pattern).is_some() as u64)
+        })
+    }
+}
 
 pub struct RedisHost {
     pool: Option<bb8_redis::bb8::Pool<bb8_redis::RedisConnectionManager>>,
+    redis_url: Option<String>,
+    backend: Arc<dyn CacheBackend>,
+    subscriptions: AsyncMutex<HashMap<u64, Subscription>>,
+    next_sub_handle: AtomicU64,
+    staged: AsyncMutex<HashMap<u64, StagedValue>>,
+    next_stage_handle: AtomicU64,
 }
 
 impl RedisHost {
     pub async fn new_from_env() -> Self {
-        let redis_url = match std::env::var("REDIS_URL") {
-            Ok(v) => {
-                if v.contains("://") {
-                    v
-                } else {
-                    format!("redis://{v}")
-                }
-            }
-            Err(_) => {
-                // Redis is optional and disabled by default.
-                return Self { pool: None };
-            }
-        };
-
-        let max_size = std::env::var("BOOSTER_REDIS_POOL_MAX")
+        let force_memory = std::env::var("BOOSTER_CACHE_FORCE_MEMORY")
             .ok()
-            .and_then(|v| v.parse::<u32>().ok())
-            .unwrap_or(32);
-
-        let manager = match bb8_redis::RedisConnectionManager::new(redis_url) {
-            Ok(m) => m,
-            Err(e) => {
-                eprintln!("Redis disabled: failed to create connection manager: {e}");
-                return Self { pool: None };
+            .as_deref()
+            .map(|v| matches!(v, "1" | "true" | "TRUE" | "yes" | "YES"))
+            .unwrap_or(false);
+
+        let redis_url = if force_memory { None } else {
+            match std::env::var("REDIS_URL") {
+                Ok(v) if v.contains("://") => Some(v),
+                Ok(v) => Some(format!("redis://{v}")),
+                Err(_) => None,
             }
         };
-        let pool = match bb8_redis::bb8::Pool::builder().max_size(max_size).build(manager).await {
-            Ok(p) => p,
-            Err(e) => {
-                eprintln!("Redis disabled: failed to build pool: {e}");
-                return Self { pool: None };
+
+        let mut pool = None;
+        if let Some(redis_url) = redis_url.clone() {
+            let max_size = std::env::var("BOOSTER_REDIS_POOL_MAX")
+                .ok()
+                .and_then(|v| v.parse::<u32>().ok())
+                .unwrap_or(32);
+
+            match bb8_redis::RedisConnectionManager::new(redis_url) {
+                Ok(manager) => match bb8_redis::bb8::Pool::builder().max_size(max_size).build(manager).await {
+                    Ok(p) => pool = Some(p),
+                    Err(e) => eprintln!("Redis disabled, falling back to in-memory cache: failed to build pool: {e}"),
+                },
+                Err(e) => {
+                    eprintln!("Redis disabled, falling back to in-memory cache: failed to create connection manager: {e}")
+                }
             }
+        }
+
+        let backend: Arc<dyn CacheBackend> = match pool.clone() {
+            Some(pool) => Arc::new(RedisBackend { pool }),
+            None => Arc::new(MemoryBackend::new()),
         };
+        let redis_url = if pool.is_some() { redis_url } else { None };
+
+        Self {
+            pool,
+            redis_url,
+            backend,
+            subscriptions: AsyncMutex::new(HashMap::new()),
+            next_sub_handle: AtomicU64::new(1),
+            staged: AsyncMutex::new(HashMap::new()),
+            next_stage_handle: AtomicU64::new(1),
+        }
+    }
 
-        Self { pool: Some(pool) }
+    /// Records `msg` into `last_error` — the *caller's* `HostState::last_error`, not a field on
+    /// `RedisHost` itself, since `RedisHost` is shared across every concurrent guest run while
+    /// `last_error` must not be (see [`crate::errors::HostState`]).
+    fn record_error(&self, last_error: &crate::errors::LastError, msg: impl Into<String>) {
+        last_error.set(msg);
     }
 
     fn disabled_error() -> bb8_redis::redis::RedisError {
@@ -54,6 +322,39 @@ impl RedisHost {
     }
 
     pub async fn get(&self, key: String) -> Result<Option<Vec<u8>>, bb8_redis::redis::RedisError> {
+        self.backend.get(key).await
+    }
+
+    pub async fn set(&self, key: String, val: Vec<u8>) -> Result<(), bb8_redis::redis::RedisError> {
+        self.backend.set(key, val).await
+    }
+
+    pub async fn set_ex(
+        &self,
+        key: String,
+        val: Vec<u8>,
+        ttl_seconds: u64,
+    ) -> Result<(), bb8_redis::redis::RedisError> {
+        self.backend.set_ex(key, val, ttl_seconds).await
+    }
+
+    pub async fn exists(&self, key: String) -> Result<bool, bb8_redis::redis::RedisError> {
+        self.backend.exists(key).await
+    }
+
+    pub async fn del(&self, key: String) -> Result<u64, bb8_redis::redis::RedisError> {
+        self.backend.del(key).await
+    }
+
+    /// Removes every key matching `pattern` in one call. `*` flushes everything, `prefix:*`
+    /// removes every key starting with `prefix:`. On the in-memory backend a trailing `*` always
+    /// means a literal prefix and any other pattern is an exact key; the Redis backend hands
+    /// `pattern` to `KEYS`, so `?`, `[...]` and a non-trailing `*` are glob syntax there.
+    pub async fn invalidate(&self, pattern: String) -> Result<u64, bb8_redis::redis::RedisError> {
+        self.backend.invalidate(pattern).await
+    }
+
+    pub async fn incr_by(&self, key: String, delta: i64) -> Result<i64, bb8_redis::redis::RedisError> {
         use bb8_redis::redis::{AsyncCommands, ErrorKind, RedisError};
 
         let Some(pool) = self.pool.as_ref() else {
@@ -63,10 +364,10 @@ impl RedisHost {
         let mut conn = pool.get().await.map_err(|e| {
             RedisError::from((ErrorKind::Io, "bb8 pool error", e.to_string()))
         })?;
-        conn.get(key).await
+        conn.incr(key, delta).await
     }
 
-    pub async fn set(&self, key: String, val: Vec<u8>) -> Result<(), bb8_redis::redis::RedisError> {
+    pub async fn expire(&self, key: String, ttl_seconds: i64) -> Result<bool, bb8_redis::redis::RedisError> {
         use bb8_redis::redis::{AsyncCommands, ErrorKind, RedisError};
 
         let Some(pool) = self.pool.as_ref() else {
@@ -76,15 +377,10 @@ impl RedisHost {
         let mut conn = pool.get().await.map_err(|e| {
             RedisError::from((ErrorKind::Io, "bb8 pool error", e.to_string()))
         })?;
-        conn.set(key, val).await
+        conn.expire(key, ttl_seconds).await
     }
 
-    pub async fn set_ex(
-        &self,
-        key: String,
-        val: Vec<u8>,
-        ttl_seconds: u64,
-    ) -> Result<(), bb8_redis::redis::RedisError> {
+    pub async fn ttl(&self, key: String) -> Result<i64, bb8_redis::redis::RedisError> {
         use bb8_redis::redis::{AsyncCommands, ErrorKind, RedisError};
 
         let Some(pool) = self.pool.as_ref() else {
@@ -94,10 +390,10 @@ impl RedisHost {
         let mut conn = pool.get().await.map_err(|e| {
             RedisError::from((ErrorKind::Io, "bb8 pool error", e.to_string()))
         })?;
-        conn.set_ex(key, val, ttl_seconds).await
+        conn.ttl(key).await
     }
 
-    pub async fn exists(&self, key: String) -> Result<bool, bb8_redis::redis::RedisError> {
+    pub async fn hset(&self, key: String, field: String, val: Vec<u8>) -> Result<(), bb8_redis::redis::RedisError> {
         use bb8_redis::redis::{AsyncCommands, ErrorKind, RedisError};
 
         let Some(pool) = self.pool.as_ref() else {
@@ -107,200 +403,1186 @@ impl RedisHost {
         let mut conn = pool.get().await.map_err(|e| {
             RedisError::from((ErrorKind::Io, "bb8 pool error", e.to_string()))
         })?;
-        conn.exists(key).await
+        conn.hset(key, field, val).await
     }
 
-    pub async fn del(&self, key: String) -> Result<u64, bb8_redis::redis::RedisError> {
-        use bb8_redis::redis::{AsyncCommands, ErrorKind, RedisError};
+    pub async fn hget(&self, key: String, field: String) -> Result<Option<Vec<u8>>, bb8_redis::redis::RedisError> {
+        use bb8_redis::redis::{AsyncCommands, ErrorKind, RedisError};
+
+        let Some(pool) = self.pool.as_ref() else {
+            return Err(Self::disabled_error());
+        };
+
+        let mut conn = pool.get().await.map_err(|e| {
+            RedisError::from((ErrorKind::Io, "bb8 pool error", e.to_string()))
+        })?;
+        conn.hget(key, field).await
+    }
+
+    pub async fn hdel(&self, key: String, field: String) -> Result<u64, bb8_redis::redis::RedisError> {
+        use bb8_redis::redis::{AsyncCommands, ErrorKind, RedisError};
+
+        let Some(pool) = self.pool.as_ref() else {
+            return Err(Self::disabled_error());
+        };
+
+        let mut conn = pool.get().await.map_err(|e| {
+            RedisError::from((ErrorKind::Io, "bb8 pool error", e.to_string()))
+        })?;
+        conn.hdel(key, field).await
+    }
+
+    pub async fn lpush(&self, key: String, val: Vec<u8>) -> Result<u64, bb8_redis::redis::RedisError> {
+        use bb8_redis::redis::{AsyncCommands, ErrorKind, RedisError};
+
+        let Some(pool) = self.pool.as_ref() else {
+            return Err(Self::disabled_error());
+        };
+
+        let mut conn = pool.get().await.map_err(|e| {
+            RedisError::from((ErrorKind::Io, "bb8 pool error", e.to_string()))
+        })?;
+        conn.lpush(key, val).await
+    }
+
+    pub async fn rpop(&self, key: String) -> Result<Option<Vec<u8>>, bb8_redis::redis::RedisError> {
+        use bb8_redis::redis::{AsyncCommands, ErrorKind, RedisError};
+
+        let Some(pool) = self.pool.as_ref() else {
+            return Err(Self::disabled_error());
+        };
+
+        let mut conn = pool.get().await.map_err(|e| {
+            RedisError::from((ErrorKind::Io, "bb8 pool error", e.to_string()))
+        })?;
+        conn.rpop(key, None).await
+    }
+
+    pub async fn llen(&self, key: String) -> Result<u64, bb8_redis::redis::RedisError> {
+        use bb8_redis::redis::{AsyncCommands, ErrorKind, RedisError};
+
+        let Some(pool) = self.pool.as_ref() else {
+            return Err(Self::disabled_error());
+        };
+
+        let mut conn = pool.get().await.map_err(|e| {
+            RedisError::from((ErrorKind::Io, "bb8 pool error", e.to_string()))
+        })?;
+        conn.llen(key).await
+    }
+
+    pub async fn zadd(&self, key: String, member: Vec<u8>, score: f64) -> Result<u64, bb8_redis::redis::RedisError> {
+        use bb8_redis::redis::{AsyncCommands, ErrorKind, RedisError};
+
+        let Some(pool) = self.pool.as_ref() else {
+            return Err(Self::disabled_error());
+        };
+
+        let mut conn = pool.get().await.map_err(|e| {
+            RedisError::from((ErrorKind::Io, "bb8 pool error", e.to_string()))
+        })?;
+        conn.zadd(key, member, score).await
+    }
+
+    pub async fn zrevrange(&self, key: String, start: isize, stop: isize) -> Result<Vec<Vec<u8>>, bb8_redis::redis::RedisError> {
+        use bb8_redis::redis::{AsyncCommands, ErrorKind, RedisError};
+
+        let Some(pool) = self.pool.as_ref() else {
+            return Err(Self::disabled_error());
+        };
+
+        let mut conn = pool.get().await.map_err(|e| {
+            RedisError::from((ErrorKind::Io, "bb8 pool error", e.to_string()))
+        })?;
+        conn.zrevrange(key, start, stop).await
+    }
+
+    pub async fn publish(&self, channel: String, msg: Vec<u8>) -> Result<i64, bb8_redis::redis::RedisError> {
+        use bb8_redis::redis::{AsyncCommands, ErrorKind, RedisError};
+
+        let Some(pool) = self.pool.as_ref() else {
+            return Err(Self::disabled_error());
+        };
+
+        let mut conn = pool.get().await.map_err(|e| {
+            RedisError::from((ErrorKind::Io, "bb8 pool error", e.to_string()))
+        })?;
+        conn.publish(channel, msg).await
+    }
+
+    /// Opens a dedicated `PubSub` connection for `channel` and spawns a task that forwards
+    /// every message payload into a buffered channel, returning a handle for `poll`/`unsubscribe`.
+    pub async fn subscribe(&self, channel: String) -> Result<u64, bb8_redis::redis::RedisError> {
+        use bb8_redis::redis::{ErrorKind, RedisError};
+        use futures_util::StreamExt;
+
+        let Some(redis_url) = self.redis_url.as_ref() else {
+            return Err(Self::disabled_error());
+        };
+
+        let client = bb8_redis::redis::Client::open(redis_url.as_str())?;
+        let mut pubsub = client.get_async_pubsub().await?;
+        pubsub.subscribe(&channel).await.map_err(|e| {
+            RedisError::from((ErrorKind::Io, "subscribe failed", e.to_string()))
+        })?;
+
+        let (tx, rx) = mpsc::channel::<Vec<u8>>(256);
+        let listen_task = tokio::spawn(async move {
+            let mut stream = pubsub.on_message();
+            while let Some(msg) = stream.next().await {
+                let payload = msg.get_payload_bytes().to_vec();
+                if tx.send(payload).await.is_err() {
+                    break;
+                }
+            }
+        });
+
+        let handle = self.next_sub_handle.fetch_add(1, Ordering::SeqCst);
+        self.subscriptions.lock().await.insert(
+            handle,
+            Subscription { rx, listen_task, last_polled: Instant::now() },
+        );
+        Ok(handle)
+    }
+
+    /// Non-blocking drain of the next buffered message for `handle`. Returns `Ok(None)` when
+    /// no message is currently available and `Err` when the handle is unknown.
+    pub async fn poll(&self, handle: u64) -> Result<Option<Vec<u8>>, ()> {
+        let mut subs = self.subscriptions.lock().await;
+        let Some(sub) = subs.get_mut(&handle) else {
+            return Err(());
+        };
+        sub.last_polled = Instant::now();
+        match sub.rx.try_recv() {
+            Ok(msg) => Ok(Some(msg)),
+            Err(mpsc::error::TryRecvError::Empty) => Ok(None),
+            Err(mpsc::error::TryRecvError::Disconnected) => Ok(None),
+        }
+    }
+
+    /// Tears down the subscription behind `handle`: dropping it aborts `listen_task` and closes
+    /// its dedicated `PubSub` connection. Returns `Err` if the handle is unknown.
+    pub async fn unsubscribe(&self, handle: u64) -> Result<(), ()> {
+        self.subscriptions.lock().await.remove(&handle).map(|_| ()).ok_or(())
+    }
+
+    /// Drops every subscription that hasn't been `poll`ed in at least `idle_timeout`, so a guest
+    /// that traps or forgets to `unsubscribe` can't permanently hold its `PubSub` connection and
+    /// listener task open.
+    async fn reap_idle_subscriptions(&self, idle_timeout: Duration) {
+        self.subscriptions
+            .lock()
+            .await
+            .retain(|_, sub| sub.last_polled.elapsed() < idle_timeout);
+    }
+
+    /// Fetches `key` and stages it for chunked retrieval, returning `(handle, total_len)` for
+    /// `redis_read_chunk` to page through.
+    pub async fn get_len(&self, key: String) -> Result<Option<(u64, usize)>, bb8_redis::redis::RedisError> {
+        let Some(val) = self.backend.get(key).await? else {
+            return Ok(None);
+        };
+        let len = val.len();
+        let handle = self.next_stage_handle.fetch_add(1, Ordering::SeqCst);
+        self.staged.lock().await.insert(handle, StagedValue::new(val));
+        Ok(Some((handle, len)))
+    }
+
+    /// Copies up to `max_len` bytes (itself capped to `STAGE_SCRATCH_BYTES`) of the value staged
+    /// under `handle`, starting at `offset`. `max_len` must be the number of bytes the caller can
+    /// actually deliver (e.g. the guest's `out_len`): the staged value is only dropped once the
+    /// returned bytes reach its end, so a caller that can't take a full `STAGE_SCRATCH_BYTES`
+    /// window in one call doesn't silently lose the remainder — it keeps re-reading from the
+    /// returned length's new offset instead.
+    pub async fn read_chunk(&self, handle: u64, offset: usize, max_len: usize) -> Result<Vec<u8>, ()> {
+        let mut staged = self.staged.lock().await;
+        let Some(sv) = staged.get_mut(&handle) else {
+            return Err(());
+        };
+        sv.last_read = Instant::now();
+        let chunk = sv.read_into_scratch(offset, max_len).to_vec();
+        let drained = offset.saturating_add(chunk.len()) >= sv.data.len();
+        if drained {
+            staged.remove(&handle);
+        }
+        Ok(chunk)
+    }
+
+    /// Drops every staged value that hasn't been `read_chunk`'d in at least `idle_timeout`, so a
+    /// guest that calls `redis_get_len` and never drains it (or traps mid-read) can't
+    /// permanently leak the staged buffer.
+    async fn reap_idle_staged(&self, idle_timeout: Duration) {
+        self.staged
+            .lock()
+            .await
+            .retain(|_, sv| sv.last_read.elapsed() < idle_timeout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A `RedisHost` with the in-memory `CacheBackend` and no Redis connection, for exercising
+    /// subscription/staged-value bookkeeping without a live server.
+    fn test_host() -> RedisHost {
+        RedisHost {
+            pool: None,
+            redis_url: None,
+            backend: Arc::new(MemoryBackend::new()),
+            subscriptions: AsyncMutex::new(HashMap::new()),
+            next_sub_handle: AtomicU64::new(1),
+            staged: AsyncMutex::new(HashMap::new()),
+            next_stage_handle: AtomicU64::new(1),
+        }
+    }
+
+    fn dummy_subscription(last_polled: Instant) -> Subscription {
+        let (_tx, rx) = mpsc::channel(1);
+        Subscription {
+            rx,
+            listen_task: tokio::spawn(async {}),
+            last_polled,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_poll_and_unsubscribe_unknown_handle_err() {
+        let host = test_host();
+        assert_eq!(host.poll(999).await, Err(()));
+        assert_eq!(host.unsubscribe(999).await, Err(()));
+    }
+
+    #[tokio::test]
+    async fn test_unsubscribe_removes_subscription() {
+        let host = test_host();
+        host.subscriptions.lock().await.insert(1, dummy_subscription(Instant::now()));
+
+        assert_eq!(host.unsubscribe(1).await, Ok(()));
+        assert!(host.subscriptions.lock().await.is_empty());
+        // Already gone: a second unsubscribe of the same handle fails.
+        assert_eq!(host.unsubscribe(1).await, Err(()));
+    }
+
+    #[tokio::test]
+    async fn test_poll_drains_buffered_message_and_refreshes_last_polled() {
+        let host = test_host();
+        let (tx, rx) = mpsc::channel(1);
+        tx.send(b"hello".to_vec()).await.unwrap();
+        let stale = Instant::now() - Duration::from_secs(60);
+        host.subscriptions.lock().await.insert(
+            1,
+            Subscription { rx, listen_task: tokio::spawn(async {}), last_polled: stale },
+        );
+
+        assert_eq!(host.poll(1).await, Ok(Some(b"hello".to_vec())));
+        assert_eq!(host.poll(1).await, Ok(None));
+        assert!(host.subscriptions.lock().await.get(&1).unwrap().last_polled > stale);
+    }
+
+    #[tokio::test]
+    async fn test_reap_idle_subscriptions_drops_only_stale_entries() {
+        let host = test_host();
+        host.subscriptions
+            .lock()
+            .await
+            .insert(1, dummy_subscription(Instant::now() - Duration::from_secs(60)));
+        host.subscriptions.lock().await.insert(2, dummy_subscription(Instant::now()));
+
+        host.reap_idle_subscriptions(Duration::from_secs(30)).await;
+
+        let subs = host.subscriptions.lock().await;
+        assert_eq!(subs.len(), 1);
+        assert!(subs.contains_key(&2));
+    }
+
+    #[tokio::test]
+    async fn test_get_len_and_read_chunk_drains_and_removes_staged_value() {
+        let host = test_host();
+        host.set("k".to_owned(), b"0123456789".to_vec()).await.unwrap();
+
+        let (handle, len) = host.get_len("k".to_owned()).await.unwrap().unwrap();
+        assert_eq!(len, 10);
+        assert!(host.staged.lock().await.contains_key(&handle));
+
+        let chunk = host.read_chunk(handle, 0, STAGE_SCRATCH_BYTES).await.unwrap();
+        assert_eq!(chunk, b"0123456789");
+        // The single read above reached the end of the value, so it's reclaimed immediately.
+        assert!(!host.staged.lock().await.contains_key(&handle));
+        assert_eq!(host.read_chunk(handle, 0, STAGE_SCRATCH_BYTES).await, Err(()));
+    }
+
+    #[tokio::test]
+    async fn test_read_chunk_keeps_staged_value_when_out_len_is_smaller_than_the_chunk() {
+        let host = test_host();
+        host.set("k".to_owned(), b"0123456789".to_vec()).await.unwrap();
+        let (handle, _len) = host.get_len("k".to_owned()).await.unwrap().unwrap();
+
+        // A guest whose `out_len` can't take the whole staged value in one call must be able to
+        // keep reading from where it left off, not have the handle purged out from under it.
+        let first = host.read_chunk(handle, 0, 4).await.unwrap();
+        assert_eq!(first, b"0123");
+        assert!(host.staged.lock().await.contains_key(&handle));
+
+        let second = host.read_chunk(handle, 4, 4).await.unwrap();
+        assert_eq!(second, b"4567");
+        assert!(host.staged.lock().await.contains_key(&handle));
+
+        let third = host.read_chunk(handle, 8, 4).await.unwrap();
+        assert_eq!(third, b"89");
+        assert!(!host.staged.lock().await.contains_key(&handle));
+    }
+
+    #[tokio::test]
+    async fn test_reap_idle_staged_drops_only_stale_entries() {
+        let host = test_host();
+        host.staged.lock().await.insert(1, {
+            let mut sv = StagedValue::new(b"stale".to_vec());
+            sv.last_read = Instant::now() - Duration::from_secs(60);
+            sv
+        });
+        host.staged.lock().await.insert(2, StagedValue::new(b"fresh".to_vec()));
+
+        host.reap_idle_staged(Duration::from_secs(30)).await;
+
+        let staged = host.staged.lock().await;
+        assert_eq!(staged.len(), 1);
+        assert!(staged.contains_key(&2));
+    }
+}
+
+/// Periodically sweeps `redis`'s subscriptions and staged values for ones idle past
+/// `BOOSTER_REDIS_IDLE_MS` (default 30s), stopping once `redis` itself is dropped. Mirrors
+/// `postgres::spawn_tx_idle_sweeper`.
+fn spawn_redis_idle_sweeper(redis: Weak<RedisHost>) {
+    let idle_timeout = std::env::var("BOOSTER_REDIS_IDLE_MS")
+        .ok()
+        .and_then(|v| v.parse::<u64>().ok())
+        .map(Duration::from_millis)
+        .unwrap_or(Duration::from_secs(30));
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(5));
+        loop {
+            interval.tick().await;
+            let Some(redis) = redis.upgrade() else {
+                return;
+            };
+            redis.reap_idle_subscriptions(idle_timeout).await;
+            redis.reap_idle_staged(idle_timeout).await;
+        }
+    });
+}
+
+fn read_guest_bytes(
+    caller: &mut wasmtime::Caller<'_, HostState>,
+    ptr: i32,
+    len: i32,
+) -> Result<Vec<u8>, ()> {
+    if ptr < 0 || len < 0 {
+        return Err(());
+    }
+    let Some(mem) = caller.get_export("memory").and_then(|e| e.into_memory()) else {
+        return Err(());
+    };
+    let mut buf = vec![0u8; len as usize];
+    mem.read(&mut *caller, ptr as usize, &mut buf).map_err(|_| ())?;
+    Ok(buf)
+}
+
+fn write_guest_bytes(
+    caller: &mut wasmtime::Caller<'_, HostState>,
+    ptr: i32,
+    data: &[u8],
+) -> Result<(), ()> {
+    if ptr < 0 {
+        return Err(());
+    }
+    let Some(mem) = caller.get_export("memory").and_then(|e| e.into_memory()) else {
+        return Err(());
+    };
+    mem.write(&mut *caller, ptr as usize, data).map_err(|_| ())?;
+    Ok(())
+}
+
+pub fn add_redis_to_linker(linker: &mut Linker<HostState>, redis: Arc<RedisHost>) -> Result<(), Error> {
+    let redis_get_host = redis.clone();
+    linker.func_wrap_async(
+        "bosbase_redis",
+        "redis_get",
+        move |mut caller: wasmtime::Caller<'_, HostState>, (kptr, klen, out_ptr, out_len): (i32, i32, i32, i32)| {
+            let redis = redis_get_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                if out_len < 0 {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+                let key = match read_guest_bytes(&mut caller, kptr, klen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let key = match String::from_utf8(key) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+
+                let Some(val) = (match redis.get(key).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("redis_get: {e}"));
+                        return Ok(HostErrorCode::BackendError.code());
+                    }
+                }) else {
+                    return Ok(HostErrorCode::NotFound.code());
+                };
+                if (val.len() as i32) > out_len {
+                    return Ok(HostErrorCode::Truncated.code());
+                }
+                if write_guest_bytes(&mut caller, out_ptr, &val).is_err() {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+                Ok(val.len() as i32)
+            })
+        },
+    )?;
+
+    let redis_set_host = redis.clone();
+    linker.func_wrap_async(
+        "bosbase_redis",
+        "redis_set",
+        move |mut caller: wasmtime::Caller<'_, HostState>, (kptr, klen, vptr, vlen): (i32, i32, i32, i32)| {
+            let redis = redis_set_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                let key = match read_guest_bytes(&mut caller, kptr, klen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let val = match read_guest_bytes(&mut caller, vptr, vlen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let key = match String::from_utf8(key) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+
+                match redis.set(key, val).await {
+                    Ok(()) => Ok(HostErrorCode::Ok.code()),
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("redis_set: {e}"));
+                        Ok(HostErrorCode::BackendError.code())
+                    }
+                }
+            })
+        },
+    )?;
+
+    let redis_set_ex_host = redis.clone();
+    linker.func_wrap_async(
+        "bosbase_redis",
+        "redis_set_ex",
+        move |mut caller: wasmtime::Caller<'_, HostState>,
+              (kptr, klen, vptr, vlen, ttl_s): (i32, i32, i32, i32, i64)| {
+            let redis = redis_set_ex_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                if ttl_s < 0 {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+                let key = match read_guest_bytes(&mut caller, kptr, klen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let val = match read_guest_bytes(&mut caller, vptr, vlen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let key = match String::from_utf8(key) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+
+                match redis.set_ex(key, val, ttl_s as u64).await {
+                    Ok(()) => Ok(HostErrorCode::Ok.code()),
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("redis_set_ex: {e}"));
+                        Ok(HostErrorCode::BackendError.code())
+                    }
+                }
+            })
+        },
+    )?;
+
+    let redis_exists_host = redis.clone();
+    linker.func_wrap_async(
+        "bosbase_redis",
+        "redis_exists",
+        move |mut caller: wasmtime::Caller<'_, HostState>, (kptr, klen): (i32, i32)| {
+            let redis = redis_exists_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                let key = match read_guest_bytes(&mut caller, kptr, klen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let key = match String::from_utf8(key) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+
+                match redis.exists(key).await {
+                    Ok(true) => Ok(1),
+                    Ok(false) => Ok(0),
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("redis_exists: {e}"));
+                        Ok(HostErrorCode::BackendError.code())
+                    }
+                }
+            })
+        },
+    )?;
+
+    let redis_del_host = redis.clone();
+    linker.func_wrap_async(
+        "bosbase_redis",
+        "redis_del",
+        move |mut caller: wasmtime::Caller<'_, HostState>, (kptr, klen): (i32, i32)| {
+            let redis = redis_del_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                let key = match read_guest_bytes(&mut caller, kptr, klen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let key = match String::from_utf8(key) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+
+                match redis.del(key).await {
+                    Ok(n) => Ok(n.min(i32::MAX as u64) as i32),
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("redis_del: {e}"));
+                        Ok(HostErrorCode::BackendError.code())
+                    }
+                }
+            })
+        },
+    )?;
+
+    let redis_incr_by_host = redis.clone();
+    linker.func_wrap_async(
+        "bosbase_redis",
+        "redis_incr_by",
+        move |mut caller: wasmtime::Caller<'_, HostState>, (kptr, klen, delta): (i32, i32, i64)| {
+            let redis = redis_incr_by_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                let key = match read_guest_bytes(&mut caller, kptr, klen) {
+                    Ok(b) => b,
+                    Err(_) => {
+                        redis.record_error(&last_error, "redis_incr_by: cannot read key from guest memory");
+                        return Ok(0i64);
+                    }
+                };
+                let key = match String::from_utf8(key) {
+                    Ok(s) => s,
+                    Err(_) => {
+                        redis.record_error(&last_error, "redis_incr_by: key is not valid UTF-8");
+                        return Ok(0i64);
+                    }
+                };
+
+                // `incr_by`'s success range is the full `i64`, including negative counters, so
+                // unlike the rest of this family it can't also use negative values as
+                // `HostErrorCode` sentinels; a read failure is reported as a guest-visible `0`
+                // (Redis's own "key didn't exist" starting point) with the real cause recorded
+                // via `bosbase_host_last_error` instead.
+                match redis.incr_by(key, delta).await {
+                    Ok(n) => Ok(n),
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("redis_incr_by: {e}"));
+                        Ok(0i64)
+                    }
+                }
+            })
+        },
+    )?;
+
+    let redis_expire_host = redis.clone();
+    linker.func_wrap_async(
+        "bosbase_redis",
+        "redis_expire",
+        move |mut caller: wasmtime::Caller<'_, HostState>, (kptr, klen, ttl_s): (i32, i32, i64)| {
+            let redis = redis_expire_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                let key = match read_guest_bytes(&mut caller, kptr, klen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code() as i64),
+                };
+                let key = match String::from_utf8(key) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code() as i64),
+                };
+
+                match redis.expire(key, ttl_s).await {
+                    Ok(true) => Ok(1),
+                    Ok(false) => Ok(0),
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("redis_expire: {e}"));
+                        Ok(HostErrorCode::BackendError.code() as i64)
+                    }
+                }
+            })
+        },
+    )?;
 
-        let Some(pool) = self.pool.as_ref() else {
-            return Err(Self::disabled_error());
-        };
+    let redis_ttl_host = redis.clone();
+    linker.func_wrap_async(
+        "bosbase_redis",
+        "redis_ttl",
+        move |mut caller: wasmtime::Caller<'_, HostState>, (kptr, klen): (i32, i32)| {
+            let redis = redis_ttl_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                // Redis's own `TTL` reply already uses `-2` ("no such key") and `-1` ("no
+                // expiry") as meaningful, non-error results, colliding with what would otherwise
+                // be `HostErrorCode::Truncated`/`NotFound` — so this keeps passing the native
+                // reply through rather than reinterpreting it, and only a read/backend failure
+                // (which can't otherwise arise here) would go through `HostErrorCode`.
+                let key = match read_guest_bytes(&mut caller, kptr, klen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code() as i64),
+                };
+                let key = match String::from_utf8(key) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code() as i64),
+                };
 
-        let mut conn = pool.get().await.map_err(|e| {
-            RedisError::from((ErrorKind::Io, "bb8 pool error", e.to_string()))
-        })?;
-        conn.del(key).await
-    }
-}
+                match redis.ttl(key).await {
+                    Ok(n) => Ok(n),
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("redis_ttl: {e}"));
+                        Ok(HostErrorCode::BackendError.code() as i64)
+                    }
+                }
+            })
+        },
+    )?;
 
-fn read_guest_bytes(
-    caller: &mut wasmtime::Caller<'_, WasiP1Ctx>,
-    ptr: i32,
-    len: i32,
-) -> Result<Vec<u8>, ()> {
-    if ptr < 0 || len < 0 {
-        return Err(());
-    }
-    let Some(mem) = caller.get_export("memory").and_then(|e| e.into_memory()) else {
-        return Err(());
-    };
-    let mut buf = vec![0u8; len as usize];
-    mem.read(&mut *caller, ptr as usize, &mut buf).map_err(|_| ())?;
-    Ok(buf)
-}
+    let redis_hset_host = redis.clone();
+    linker.func_wrap_async(
+        "bosbase_redis",
+        "redis_hset",
+        move |mut caller: wasmtime::Caller<'_, HostState>,
+              (kptr, klen, fptr, flen, vptr, vlen): (i32, i32, i32, i32, i32, i32)| {
+            let redis = redis_hset_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                let key = match read_guest_bytes(&mut caller, kptr, klen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let field = match read_guest_bytes(&mut caller, fptr, flen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let val = match read_guest_bytes(&mut caller, vptr, vlen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let key = match String::from_utf8(key) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let field = match String::from_utf8(field) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
 
-fn write_guest_bytes(
-    caller: &mut wasmtime::Caller<'_, WasiP1Ctx>,
-    ptr: i32,
-    data: &[u8],
-) -> Result<(), ()> {
-    if ptr < 0 {
-        return Err(());
-    }
-    let Some(mem) = caller.get_export("memory").and_then(|e| e.into_memory()) else {
-        return Err(());
-    };
-    mem.write(&mut *caller, ptr as usize, data).map_err(|_| ())?;
-    Ok(())
-}
+                match redis.hset(key, field, val).await {
+                    Ok(()) => Ok(HostErrorCode::Ok.code()),
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("redis_hset: {e}"));
+                        Ok(HostErrorCode::BackendError.code())
+                    }
+                }
+            })
+        },
+    )?;
 
-pub fn add_redis_to_linker(linker: &mut Linker<WasiP1Ctx>, redis: Arc<RedisHost>) -> Result<(), Error> {
-    let redis_get_host = redis.clone();
+    let redis_hget_host = redis.clone();
     linker.func_wrap_async(
         "bosbase_redis",
-        "redis_get",
-        move |mut caller: wasmtime::Caller<'_, WasiP1Ctx>, (kptr, klen, out_ptr, out_len): (i32, i32, i32, i32)| {
-            let redis = redis_get_host.clone();
+        "redis_hget",
+        move |mut caller: wasmtime::Caller<'_, HostState>,
+              (kptr, klen, fptr, flen, out_ptr, out_len): (i32, i32, i32, i32, i32, i32)| {
+            let redis = redis_hget_host.clone();
             Box::new(async move {
+                let last_error = caller.data().last_error.clone();
                 if out_len < 0 {
-                    return Ok(-3);
+                    return Ok(HostErrorCode::BadArgs.code());
                 }
                 let key = match read_guest_bytes(&mut caller, kptr, klen) {
                     Ok(b) => b,
-                    Err(_) => return Ok(-3),
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let field = match read_guest_bytes(&mut caller, fptr, flen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
                 };
                 let key = match String::from_utf8(key) {
                     Ok(s) => s,
-                    Err(_) => return Ok(-3),
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let field = match String::from_utf8(field) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
                 };
 
-                let Some(val) = (match redis.get(key).await {
+                let Some(val) = (match redis.hget(key, field).await {
                     Ok(v) => v,
-                    Err(_) => return Ok(-4),
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("redis_hget: {e}"));
+                        return Ok(HostErrorCode::BackendError.code());
+                    }
                 }) else {
-                    return Ok(-1);
+                    return Ok(HostErrorCode::NotFound.code());
                 };
                 if (val.len() as i32) > out_len {
-                    return Ok(-2);
+                    return Ok(HostErrorCode::Truncated.code());
                 }
                 if write_guest_bytes(&mut caller, out_ptr, &val).is_err() {
-                    return Ok(-3);
+                    return Ok(HostErrorCode::BadArgs.code());
                 }
                 Ok(val.len() as i32)
             })
         },
     )?;
 
-    let redis_set_host = redis.clone();
+    let redis_hdel_host = redis.clone();
     linker.func_wrap_async(
         "bosbase_redis",
-        "redis_set",
-        move |mut caller: wasmtime::Caller<'_, WasiP1Ctx>, (kptr, klen, vptr, vlen): (i32, i32, i32, i32)| {
-            let redis = redis_set_host.clone();
+        "redis_hdel",
+        move |mut caller: wasmtime::Caller<'_, HostState>, (kptr, klen, fptr, flen): (i32, i32, i32, i32)| {
+            let redis = redis_hdel_host.clone();
             Box::new(async move {
+                let last_error = caller.data().last_error.clone();
                 let key = match read_guest_bytes(&mut caller, kptr, klen) {
                     Ok(b) => b,
-                    Err(_) => return Ok(-2),
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let field = match read_guest_bytes(&mut caller, fptr, flen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let key = match String::from_utf8(key) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let field = match String::from_utf8(field) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+
+                match redis.hdel(key, field).await {
+                    Ok(n) => Ok(n.min(i32::MAX as u64) as i32),
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("redis_hdel: {e}"));
+                        Ok(HostErrorCode::BackendError.code())
+                    }
+                }
+            })
+        },
+    )?;
+
+    let redis_lpush_host = redis.clone();
+    linker.func_wrap_async(
+        "bosbase_redis",
+        "redis_lpush",
+        move |mut caller: wasmtime::Caller<'_, HostState>, (kptr, klen, vptr, vlen): (i32, i32, i32, i32)| {
+            let redis = redis_lpush_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                let key = match read_guest_bytes(&mut caller, kptr, klen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
                 };
                 let val = match read_guest_bytes(&mut caller, vptr, vlen) {
                     Ok(b) => b,
-                    Err(_) => return Ok(-2),
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
                 };
                 let key = match String::from_utf8(key) {
                     Ok(s) => s,
-                    Err(_) => return Ok(-2),
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
                 };
 
-                match redis.set(key, val).await {
-                    Ok(()) => Ok(0),
-                    Err(_) => Ok(-1),
+                match redis.lpush(key, val).await {
+                    Ok(n) => Ok(n.min(i32::MAX as u64) as i32),
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("redis_lpush: {e}"));
+                        Ok(HostErrorCode::BackendError.code())
+                    }
                 }
             })
         },
     )?;
 
-    let redis_set_ex_host = redis.clone();
+    let redis_rpop_host = redis.clone();
     linker.func_wrap_async(
         "bosbase_redis",
-        "redis_set_ex",
-        move |mut caller: wasmtime::Caller<'_, WasiP1Ctx>,
-              (kptr, klen, vptr, vlen, ttl_s): (i32, i32, i32, i32, i64)| {
-            let redis = redis_set_ex_host.clone();
+        "redis_rpop",
+        move |mut caller: wasmtime::Caller<'_, HostState>, (kptr, klen, out_ptr, out_len): (i32, i32, i32, i32)| {
+            let redis = redis_rpop_host.clone();
             Box::new(async move {
-                if ttl_s < 0 {
-                    return Ok(-2);
+                let last_error = caller.data().last_error.clone();
+                if out_len < 0 {
+                    return Ok(HostErrorCode::BadArgs.code());
                 }
                 let key = match read_guest_bytes(&mut caller, kptr, klen) {
                     Ok(b) => b,
-                    Err(_) => return Ok(-2),
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
                 };
-                let val = match read_guest_bytes(&mut caller, vptr, vlen) {
+                let key = match String::from_utf8(key) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+
+                let Some(val) = (match redis.rpop(key).await {
+                    Ok(v) => v,
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("redis_rpop: {e}"));
+                        return Ok(HostErrorCode::BackendError.code());
+                    }
+                }) else {
+                    return Ok(HostErrorCode::NotFound.code());
+                };
+                if (val.len() as i32) > out_len {
+                    return Ok(HostErrorCode::Truncated.code());
+                }
+                if write_guest_bytes(&mut caller, out_ptr, &val).is_err() {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+                Ok(val.len() as i32)
+            })
+        },
+    )?;
+
+    let redis_llen_host = redis.clone();
+    linker.func_wrap_async(
+        "bosbase_redis",
+        "redis_llen",
+        move |mut caller: wasmtime::Caller<'_, HostState>, (kptr, klen): (i32, i32)| {
+            let redis = redis_llen_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                let key = match read_guest_bytes(&mut caller, kptr, klen) {
                     Ok(b) => b,
-                    Err(_) => return Ok(-2),
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
                 };
                 let key = match String::from_utf8(key) {
                     Ok(s) => s,
-                    Err(_) => return Ok(-2),
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
                 };
 
-                match redis.set_ex(key, val, ttl_s as u64).await {
-                    Ok(()) => Ok(0),
-                    Err(_) => Ok(-1),
+                match redis.llen(key).await {
+                    Ok(n) => Ok(n.min(i32::MAX as u64) as i32),
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("redis_llen: {e}"));
+                        Ok(HostErrorCode::BackendError.code())
+                    }
                 }
             })
         },
     )?;
 
-    let redis_exists_host = redis.clone();
+    let redis_zadd_host = redis.clone();
     linker.func_wrap_async(
         "bosbase_redis",
-        "redis_exists",
-        move |mut caller: wasmtime::Caller<'_, WasiP1Ctx>, (kptr, klen): (i32, i32)| {
-            let redis = redis_exists_host.clone();
+        "redis_zadd",
+        move |mut caller: wasmtime::Caller<'_, HostState>, (kptr, klen, mptr, mlen, score): (i32, i32, i32, i32, f64)| {
+            let redis = redis_zadd_host.clone();
             Box::new(async move {
+                let last_error = caller.data().last_error.clone();
                 let key = match read_guest_bytes(&mut caller, kptr, klen) {
                     Ok(b) => b,
-                    Err(_) => return Ok(-1),
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let member = match read_guest_bytes(&mut caller, mptr, mlen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
                 };
                 let key = match String::from_utf8(key) {
                     Ok(s) => s,
-                    Err(_) => return Ok(-1),
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
                 };
 
-                match redis.exists(key).await {
-                    Ok(true) => Ok(1),
-                    Ok(false) => Ok(0),
-                    Err(_) => Ok(-1),
+                match redis.zadd(key, member, score).await {
+                    Ok(n) => Ok(n.min(i32::MAX as u64) as i32),
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("redis_zadd: {e}"));
+                        Ok(HostErrorCode::BackendError.code())
+                    }
                 }
             })
         },
     )?;
 
-    let redis_del_host = redis;
+    let redis_zrevrange_host = redis.clone();
     linker.func_wrap_async(
         "bosbase_redis",
-        "redis_del",
-        move |mut caller: wasmtime::Caller<'_, WasiP1Ctx>, (kptr, klen): (i32, i32)| {
-            let redis = redis_del_host.clone();
+        "redis_zrevrange",
+        move |mut caller: wasmtime::Caller<'_, HostState>,
+              (kptr, klen, start, stop, out_ptr, out_len): (i32, i32, i32, i32, i32, i32)| {
+            let redis = redis_zrevrange_host.clone();
             Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                if out_len < 0 {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
                 let key = match read_guest_bytes(&mut caller, kptr, klen) {
                     Ok(b) => b,
-                    Err(_) => return Ok(-1),
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
                 };
                 let key = match String::from_utf8(key) {
                     Ok(s) => s,
-                    Err(_) => return Ok(-1),
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
                 };
 
-                match redis.del(key).await {
+                let members = match redis.zrevrange(key, start as isize, stop as isize).await {
+                    Ok(m) => m,
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("redis_zrevrange: {e}"));
+                        return Ok(HostErrorCode::BackendError.code());
+                    }
+                };
+
+                // Length-prefixed members: [u32 len][bytes]... so the guest can split the buffer
+                // back into individual member values without a delimiter convention.
+                let mut payload = Vec::new();
+                for member in &members {
+                    payload.extend_from_slice(&(member.len() as u32).to_le_bytes());
+                    payload.extend_from_slice(member);
+                }
+                if (payload.len() as i32) > out_len {
+                    return Ok(HostErrorCode::Truncated.code());
+                }
+                if write_guest_bytes(&mut caller, out_ptr, &payload).is_err() {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+                Ok(payload.len() as i32)
+            })
+        },
+    )?;
+
+    let redis_publish_host = redis.clone();
+    linker.func_wrap_async(
+        "bosbase_redis",
+        "redis_publish",
+        move |mut caller: wasmtime::Caller<'_, HostState>,
+              (cptr, clen, mptr, mlen): (i32, i32, i32, i32)| {
+            let redis = redis_publish_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                let channel = match read_guest_bytes(&mut caller, cptr, clen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let channel = match String::from_utf8(channel) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let msg = match read_guest_bytes(&mut caller, mptr, mlen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+
+                match redis.publish(channel, msg).await {
+                    Ok(n) => Ok(n.clamp(0, i32::MAX as i64) as i32),
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("redis_publish: {e}"));
+                        Ok(HostErrorCode::BackendError.code())
+                    }
+                }
+            })
+        },
+    )?;
+
+    let redis_subscribe_host = redis.clone();
+    linker.func_wrap_async(
+        "bosbase_redis",
+        "redis_subscribe",
+        move |mut caller: wasmtime::Caller<'_, HostState>, (cptr, clen): (i32, i32)| {
+            let redis = redis_subscribe_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                let channel = match read_guest_bytes(&mut caller, cptr, clen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code() as i64),
+                };
+                let channel = match String::from_utf8(channel) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code() as i64),
+                };
+
+                match redis.subscribe(channel).await {
+                    Ok(handle) => Ok(handle as i64),
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("redis_subscribe: {e}"));
+                        Ok(HostErrorCode::BackendError.code() as i64)
+                    }
+                }
+            })
+        },
+    )?;
+
+    let cache_invalidate_host = redis.clone();
+    linker.func_wrap_async(
+        "bosbase_redis",
+        "cache_invalidate",
+        move |mut caller: wasmtime::Caller<'_, HostState>, (pptr, plen): (i32, i32)| {
+            let redis = cache_invalidate_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                let pattern = match read_guest_bytes(&mut caller, pptr, plen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+                let pattern = match String::from_utf8(pattern) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code()),
+                };
+
+                match redis.invalidate(pattern).await {
                     Ok(n) => Ok(n.min(i32::MAX as u64) as i32),
-                    Err(_) => Ok(-1),
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("cache_invalidate: {e}"));
+                        Ok(HostErrorCode::BackendError.code())
+                    }
+                }
+            })
+        },
+    )?;
+
+    let redis_poll_host = redis.clone();
+    linker.func_wrap_async(
+        "bosbase_redis",
+        "redis_poll",
+        move |mut caller: wasmtime::Caller<'_, HostState>, (sub_handle, out_ptr, out_len): (i64, i32, i32)| {
+            let redis = redis_poll_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                if sub_handle < 0 || out_len < 0 {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+
+                let msg = match redis.poll(sub_handle as u64).await {
+                    Ok(Some(msg)) => msg,
+                    Ok(None) => return Ok(HostErrorCode::NotFound.code()),
+                    Err(()) => {
+                        redis.record_error(&last_error, "redis_poll: unknown subscription handle");
+                        return Ok(HostErrorCode::BadArgs.code());
+                    }
+                };
+
+                if (msg.len() as i32) > out_len {
+                    return Ok(HostErrorCode::Truncated.code());
+                }
+                if write_guest_bytes(&mut caller, out_ptr, &msg).is_err() {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+                Ok(msg.len() as i32)
+            })
+        },
+    )?;
+
+    let redis_get_len_host = redis.clone();
+    linker.func_wrap_async(
+        "bosbase_redis",
+        "redis_get_len",
+        move |mut caller: wasmtime::Caller<'_, HostState>, (kptr, klen): (i32, i32)| {
+            let redis = redis_get_len_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                let key = match read_guest_bytes(&mut caller, kptr, klen) {
+                    Ok(b) => b,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code() as i64),
+                };
+                let key = match String::from_utf8(key) {
+                    Ok(s) => s,
+                    Err(_) => return Ok(HostErrorCode::BadArgs.code() as i64),
+                };
+
+                // Single i64 return, packed as `handle << 32 | len`: both halves are
+                // non-negative so the packed value is always >= 0, leaving negative returns
+                // unambiguous as sign-extended `HostErrorCode`s.
+                match redis.get_len(key).await {
+                    Ok(Some((handle, len))) => Ok(((handle as i64) << 32) | (len as i64 & 0xFFFF_FFFF)),
+                    Ok(None) => Ok(HostErrorCode::NotFound.code() as i64),
+                    Err(e) => {
+                        redis.record_error(&last_error, format!("redis_get_len: {e}"));
+                        Ok(HostErrorCode::BackendError.code() as i64)
+                    }
+                }
+            })
+        },
+    )?;
+
+    let redis_read_chunk_host = redis.clone();
+    linker.func_wrap_async(
+        "bosbase_redis",
+        "redis_read_chunk",
+        move |mut caller: wasmtime::Caller<'_, HostState>,
+              (handle, offset, out_ptr, out_len): (i64, i64, i32, i32)| {
+            let redis = redis_read_chunk_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                if handle < 0 || offset < 0 || out_len < 0 {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+
+                let chunk = match redis.read_chunk(handle as u64, offset as usize, out_len as usize).await {
+                    Ok(c) => c,
+                    Err(()) => {
+                        redis.record_error(&last_error, "redis_read_chunk: unknown staging handle");
+                        return Ok(HostErrorCode::NotFound.code());
+                    }
+                };
+
+                if write_guest_bytes(&mut caller, out_ptr, &chunk).is_err() {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+                Ok(chunk.len() as i32)
+            })
+        },
+    )?;
+
+    let redis_unsubscribe_host = redis.clone();
+    linker.func_wrap_async(
+        "bosbase_redis",
+        "redis_unsubscribe",
+        move |caller: wasmtime::Caller<'_, HostState>, (sub_handle,): (i64,)| {
+            let redis = redis_unsubscribe_host.clone();
+            Box::new(async move {
+                let last_error = caller.data().last_error.clone();
+                if sub_handle < 0 {
+                    return Ok(HostErrorCode::BadArgs.code());
+                }
+                match redis.unsubscribe(sub_handle as u64).await {
+                    Ok(()) => Ok(HostErrorCode::Ok.code()),
+                    Err(()) => {
+                        redis.record_error(&last_error, "redis_unsubscribe: unknown subscription handle");
+                        Ok(HostErrorCode::NotFound.code())
+                    }
                 }
             })
         },
     )?;
 
+    spawn_redis_idle_sweeper(Arc::downgrade(&redis));
+
     Ok(())
 }
 
@@ -341,8 +1623,8 @@ mod redis_tests {
         config.async_support(true);
         let engine = Engine::new(&config).expect("engine");
 
-        let mut linker: Linker<WasiP1Ctx> = Linker::new(&engine);
-        wasmtime_wasi::p1::add_to_linker_async(&mut linker, |cx| cx).expect("add wasi");
+        let mut linker: Linker<HostState> = Linker::new(&engine);
+        wasmtime_wasi::p1::add_to_linker_async(&mut linker, |state: &mut HostState| &mut state.wasi).expect("add wasi");
         add_redis_to_linker(&mut linker, redis.clone()).expect("add redis");
 
         // WAT guest:
@@ -378,8 +1660,8 @@ mod redis_tests {
             .stderr(stderr_pipe)
             .build_p1();
 
-        let mut store: Store<WasiP1Ctx> = Store::new(&engine, WasiCtx::builder().build_p1());
-        *store.data_mut() = wasi;
+        let mut store: Store<HostState> = Store::new(&engine, HostState::new(WasiCtx::builder().build_p1()));
+        store.data_mut().wasi = wasi;
 
         store.set_fuel(u64::MAX).expect("fuel");
         store.fuel_async_yield_interval(Some(10000)).expect("yield");